@@ -0,0 +1,31 @@
+//! Benchmark for host set matcher
+#![expect(missing_docs, unused_crate_dependencies)]
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use tower_allowed_hosts::matcher::{HostSet, Matcher as _};
+
+fn host_set(c: &mut Criterion) {
+    let mut group = c.benchmark_group("host_set");
+    for size in [100, 10_000, 100_000] {
+        let matcher = (0..size)
+            .map(|index| format!("host{index}.example.com"))
+            .collect::<HostSet>();
+        let hit = format!("host{}.example.com", size - 1);
+        group.bench_with_input(BenchmarkId::new("hit", size), &hit, |b, host| {
+            b.iter(|| matcher.matches_value(black_box(host)));
+        });
+        group.bench_with_input(
+            BenchmarkId::new("miss", size),
+            &"unknown.example.com",
+            |b, host| {
+                b.iter(|| matcher.matches_value(black_box(host)));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, host_set);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+#!/usr/bin/env python3
+"""Generate `src/psl/list.rs` from the Public Suffix List.
+
+Usage:
+    scripts/generate_psl.py [SOURCE]
+
+SOURCE can be a local path or an URL. When omitted the list is downloaded from
+https://publicsuffix.org/list/public_suffix_list.dat. Rules are converted to
+their ASCII (punycode) form since hosts received by the layer are ASCII.
+"""
+
+import pathlib
+import sys
+import urllib.request
+
+DEFAULT_SOURCE = "https://publicsuffix.org/list/public_suffix_list.dat"
+OUTPUT = pathlib.Path(__file__).resolve().parent.parent / "src" / "psl" / "list.rs"
+
+
+def read_source(source):
+    if source.startswith(("http://", "https://")):
+        with urllib.request.urlopen(source) as response:
+            return response.read().decode("utf-8")
+    return pathlib.Path(source).read_text(encoding="utf-8")
+
+
+def to_ascii(label):
+    if label == "*" or label.isascii():
+        return label.lower()
+    try:
+        return label.encode("idna").decode("ascii")
+    except UnicodeError:
+        return "xn--" + label.lower().encode("punycode").decode("ascii")
+
+
+def to_ascii_rule(rule):
+    return ".".join(to_ascii(label) for label in rule.split("."))
+
+
+def parse(content):
+    rules, wildcards, exceptions = set(), set(), set()
+    for line in content.splitlines():
+        line = line.strip()
+        if not line or line.startswith("//"):
+            continue
+        rule = line.split()[0]
+        if rule.startswith("!"):
+            exceptions.add(to_ascii_rule(rule[1:]))
+        elif rule.startswith("*."):
+            wildcards.add(to_ascii_rule(rule[2:]))
+        else:
+            rules.add(to_ascii_rule(rule))
+    return sorted(rules), sorted(wildcards), sorted(exceptions)
+
+
+def render_array(name, doc, values):
+    lines = [f"/// {doc}", f"pub(super) static {name}: &[&str] = &["]
+    lines.extend(f'    "{value}",' for value in values)
+    lines.append("];")
+    return "\n".join(lines)
+
+
+def main():
+    source = sys.argv[1] if len(sys.argv) > 1 else DEFAULT_SOURCE
+    rules, wildcards, exceptions = parse(read_source(source))
+    output = "\n\n".join(
+        [
+            "// @generated by scripts/generate_psl.py. Do not edit manually.",
+            render_array("RULES", "Normal rules of public suffix list", rules),
+            render_array(
+                "WILDCARD_RULES",
+                "Wildcard rules of public suffix list without leading `*.`",
+                wildcards,
+            ),
+            render_array(
+                "EXCEPTION_RULES",
+                "Exception rules of public suffix list without leading `!`",
+                exceptions,
+            ),
+        ]
+    )
+    OUTPUT.write_text(output + "\n", encoding="utf-8")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,21 +1,43 @@
+use crate::HostSource;
+
 /// Enum for different error
 #[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum Error {
     /// error raised when host is not allowed
     HostNotAllowed(String),
+    /// error raised when host is denied by a rule
+    HostDenied {
+        /// host which is denied
+        host: String,
+        /// rule which denied host
+        rule: String,
+    },
+    /// error raised when host can be confused with other host
+    ConfusableHost(String),
     /// error when passed forwarded header is invalid
-    InvalidForwardedHeader,
+    InvalidForwardedHeader(InvalidForwardedKind),
+    /// error when `X-Forwarded-*` header with provided name is not visible
+    /// ascii
+    InvalidXForwardedHeader(String),
+    /// error when forwarded header is sent by peer which is not trusted proxy
+    UntrustedForwarded,
+    /// error raised when host sources of request disagree, contains host of
+    /// each source starting with resolved host
+    ConflictingHost(Vec<(HostSource, String)>),
     /// error when passed host header is invalid
-    InvalidHost,
+    InvalidHost(InvalidHostKind),
     /// error when passed host header is missing
     MissingHost,
     /// error when there is multiple host header
     MultipleHostHeader,
     /// error when uri is missing along with host header
     MissingAuthority,
-    /// error raised when :authority value and host header mismatch
+    /// error raised when :authority value or authority of absolute-form target
+    /// and host header mismatch
     MismatchAuthorityHost,
+    /// error raised when `CONNECT` request is rejected by policy
+    ConnectNotAllowed,
     /// error raised for future http which may not be supported
     UnsupportedHttpVersion,
 }
@@ -24,14 +46,27 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
             Self::HostNotAllowed(host) => write!(f, "host {host} not allowed"),
-            Self::InvalidForwardedHeader => write!(f, "invalid forwarded header"),
-            Self::InvalidHost => write!(f, "invalid host"),
+            Self::HostDenied { host, rule } => write!(f, "host {host} denied by {rule}"),
+            Self::ConfusableHost(host) => write!(f, "host {host} is confusable"),
+            Self::InvalidForwardedHeader(kind) => write!(f, "invalid forwarded header: {kind}"),
+            Self::InvalidXForwardedHeader(name) => write!(f, "invalid {name} header"),
+            Self::UntrustedForwarded => write!(f, "forwarded header sent by untrusted peer"),
+            Self::ConflictingHost(hosts) => {
+                write!(f, "conflicting host:")?;
+                for (index, (source, host)) in hosts.iter().enumerate() {
+                    let separator = if index == 0 { "" } else { "," };
+                    write!(f, "{separator} {source} {host}")?;
+                }
+                Ok(())
+            }
+            Self::InvalidHost(kind) => write!(f, "invalid host: {kind}"),
             Self::MissingHost => write!(f, "missing host"),
             Self::MultipleHostHeader => write!(f, "multiple host header"),
             Self::MissingAuthority => write!(f, "missing :authority pseudo header"),
             Self::MismatchAuthorityHost => {
                 write!(f, ":authority pseudo header and host header is mismatched")
             }
+            Self::ConnectNotAllowed => write!(f, "connect request not allowed"),
             Self::UnsupportedHttpVersion => {
                 write!(f, "unsupported http version")
             }
@@ -41,6 +76,121 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// Enum for reason why host is invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidHostKind {
+    /// host contains character which is not visible ascii
+    NonVisibleAscii,
+    /// host is empty
+    Empty,
+    /// host contains character which is not allowed
+    InvalidCharacter,
+    /// host contains percent encoded character
+    PercentEncoded,
+    /// host contains empty label
+    EmptyLabel,
+    /// host contains label longer than 63 characters
+    LabelTooLong,
+    /// host is longer than 253 characters
+    HostTooLong,
+    /// port is not a number between 0 and 65535
+    InvalidPort,
+    /// host is invalid IPv4 address or IP literal
+    InvalidIpAddress,
+    /// host contains invalid punycode label
+    InvalidPunycode,
+    /// host is surrounded by double quotes or contains double quote
+    Quoted,
+    /// host is surrounded by whitespace
+    Padded,
+}
+
+impl std::fmt::Display for InvalidHostKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonVisibleAscii => write!(f, "non visible ascii character"),
+            Self::Empty => write!(f, "empty host"),
+            Self::InvalidCharacter => write!(f, "invalid character"),
+            Self::PercentEncoded => write!(f, "percent encoded character"),
+            Self::EmptyLabel => write!(f, "empty label"),
+            Self::LabelTooLong => write!(f, "label too long"),
+            Self::HostTooLong => write!(f, "host too long"),
+            Self::InvalidPort => write!(f, "invalid port"),
+            Self::InvalidIpAddress => write!(f, "invalid ip address"),
+            Self::InvalidPunycode => write!(f, "invalid punycode"),
+            Self::Quoted => write!(f, "quoted host"),
+            Self::Padded => write!(f, "padded host"),
+        }
+    }
+}
+
+/// Enum for reason why forwarded header is invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidForwardedKind {
+    /// header is not valid UTF-8
+    InvalidEncoding,
+    /// parameter name is not a token
+    InvalidName,
+    /// parameter name is not followed by `=`
+    MissingValue,
+    /// parameter value is neither token nor quoted string
+    InvalidValue,
+    /// character is not allowed at its position
+    UnexpectedCharacter,
+    /// quoted string is not terminated
+    UnterminatedQuote,
+    /// quoted pair escapes character which is not allowed
+    InvalidEscape,
+    /// `for` or `by` parameter is not a valid node
+    InvalidNode,
+    /// parameter appears more than once in same element
+    DuplicateParameter,
+}
+
+impl std::fmt::Display for InvalidForwardedKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidEncoding => write!(f, "invalid encoding"),
+            Self::InvalidName => write!(f, "invalid parameter name"),
+            Self::MissingValue => write!(f, "missing parameter value"),
+            Self::InvalidValue => write!(f, "invalid parameter value"),
+            Self::UnexpectedCharacter => write!(f, "unexpected character"),
+            Self::UnterminatedQuote => write!(f, "unterminated quoted string"),
+            Self::InvalidEscape => write!(f, "invalid escape"),
+            Self::InvalidNode => write!(f, "invalid node"),
+            Self::DuplicateParameter => write!(f, "duplicate parameter"),
+        }
+    }
+}
+
+/// Enum for error raised while creating matcher from pattern
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum PatternError {
+    /// error raised when pattern is invalid
+    InvalidPattern(String),
+    /// error raised when pattern covers a public suffix
+    PublicSuffix(String),
+    /// error raised when domain is not a registrable domain
+    NotRegistrableDomain(String),
+}
+
+impl std::fmt::Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::InvalidPattern(pattern) => write!(f, "invalid pattern {pattern}"),
+            Self::PublicSuffix(pattern) => write!(f, "pattern {pattern} covers public suffix"),
+            Self::NotRegistrableDomain(domain) => {
+                write!(f, "{domain} is not a registrable domain")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatternError {}
+
 /// Enum representing host rejection
 #[cfg(feature = "axum")]
 #[non_exhaustive]
@@ -0,0 +1,905 @@
+use std::fmt::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use http::header::{FORWARDED, InvalidHeaderValue, ValueIter};
+use http::{HeaderMap, HeaderValue};
+
+use crate::error::InvalidForwardedKind;
+use crate::proxy::TrustedProxies;
+
+/// Parameters of single forwarded element in order of appearance with
+/// lowercased name and unescaped value
+pub(crate) type Pairs = Vec<(String, String)>;
+
+/// Ordered borrowed view of parameters of single element of `Forwarded`
+/// header
+///
+/// Parameters are in order of appearance with lowercased name and unescaped
+/// value. Elements parsed from header never contain duplicate name.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::forwarded::Parameters;
+///
+/// let pairs = [("by".to_string(), "proxy".to_string())];
+/// let parameters = Parameters::new(&pairs);
+/// assert_eq!(parameters.get("BY"), Some("proxy"));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Parameters<'a> {
+    pairs: &'a [(String, String)],
+}
+
+impl<'a> Parameters<'a> {
+    /// Create view over name and value pairs
+    #[must_use]
+    pub fn new(pairs: &'a [(String, String)]) -> Self {
+        Self { pairs }
+    }
+
+    /// Get value of first parameter with name compared case-insensitively
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.pairs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Check whether parameter with name is present
+    #[must_use]
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Iterate over name and value of parameters in order of appearance
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.pairs
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Number of parameters
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Check whether there is no parameter
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+/// Every element of all `Forwarded` headers of request along with connection
+/// details passed to
+/// [`ForwardedChainMatcher`](crate::matcher::ForwardedChainMatcher)
+///
+/// Elements are in order of appearance so rightmost element is added by proxy
+/// closest to service.
+#[derive(Debug, Clone)]
+pub struct ForwardedChain<'a> {
+    elements: Vec<Parameters<'a>>,
+    peer_addr: Option<IpAddr>,
+    trusted_proxies: Option<&'a TrustedProxies>,
+}
+
+impl<'a> ForwardedChain<'a> {
+    /// Create new chain from elements
+    pub fn new<I>(elements: I) -> Self
+    where
+        I: IntoIterator<Item = Parameters<'a>>,
+    {
+        Self {
+            elements: elements.into_iter().collect(),
+            peer_addr: None,
+            trusted_proxies: None,
+        }
+    }
+
+    /// Set peer address of connection
+    #[must_use]
+    pub fn with_peer_addr(mut self, peer_addr: IpAddr) -> Self {
+        self.peer_addr = Some(peer_addr);
+        self
+    }
+
+    /// Set trusted proxies configured in layer
+    #[must_use]
+    pub fn with_trusted_proxies(mut self, trusted_proxies: &'a TrustedProxies) -> Self {
+        self.trusted_proxies = Some(trusted_proxies);
+        self
+    }
+
+    /// Elements of chain in order of appearance
+    #[must_use]
+    pub fn elements(&self) -> &[Parameters<'a>] {
+        &self.elements
+    }
+
+    /// Peer address of connection if known
+    #[must_use]
+    pub fn peer_addr(&self) -> Option<IpAddr> {
+        self.peer_addr
+    }
+
+    /// Trusted proxies configured in layer if any
+    #[must_use]
+    pub fn trusted_proxies(&self) -> Option<&'a TrustedProxies> {
+        self.trusted_proxies
+    }
+}
+
+/// Single element of `Forwarded` header
+///
+/// Element is serialized using [`fmt::Display`] with parameters in order of
+/// `for`, `by`, `host`, `proto` and extensions. Value which is not a token is
+/// written as quoted string.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::forwarded::{ForwardedElement, Node};
+///
+/// let element = ForwardedElement {
+///     r#for: Some("192.0.2.43".parse::<Node>()?),
+///     host: Some("example.com".to_string()),
+///     ..ForwardedElement::default()
+/// };
+/// assert_eq!(element.to_string(), "for=192.0.2.43;host=example.com");
+/// # Ok::<(), tower_allowed_hosts::error::InvalidForwardedKind>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForwardedElement {
+    /// `for` parameter identifying client which made request to proxy
+    pub r#for: Option<Node>,
+    /// `by` parameter identifying interface where request came in to proxy
+    pub by: Option<Node>,
+    /// `host` parameter containing original value of `Host` header
+    pub host: Option<String>,
+    /// `proto` parameter containing protocol used to make request
+    pub proto: Option<String>,
+    /// other parameters in order of appearance with lowercased name
+    pub extensions: Vec<(String, String)>,
+}
+
+impl ForwardedElement {
+    /// Create typed element from parsed parameters
+    fn from_pairs(pairs: Pairs) -> Result<Self, InvalidForwardedKind> {
+        let mut element = Self::default();
+        for (name, value) in pairs {
+            match name.as_str() {
+                "for" => element.r#for = Some(value.parse()?),
+                "by" => element.by = Some(value.parse()?),
+                "host" => element.host = Some(value),
+                "proto" => {
+                    if !is_scheme(&value) {
+                        return Err(InvalidForwardedKind::InvalidValue);
+                    }
+                    element.proto = Some(value);
+                }
+                _ => element.extensions.push((name, value)),
+            }
+        }
+        Ok(element)
+    }
+}
+
+impl fmt::Display for ForwardedElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let for_value = self.r#for.as_ref().map(ToString::to_string);
+        let by_value = self.by.as_ref().map(ToString::to_string);
+        let params = [
+            ("for", for_value.as_deref()),
+            ("by", by_value.as_deref()),
+            ("host", self.host.as_deref()),
+            ("proto", self.proto.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .chain(
+            self.extensions
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+        for (index, (name, value)) in params.enumerate() {
+            if index > 0 {
+                f.write_char(';')?;
+            }
+            write!(f, "{name}=")?;
+            write_value(f, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Node of `for` or `by` parameter
+///
+/// Node is parsed using [`FromStr`] and serialized using [`fmt::Display`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// name of node
+    pub name: NodeName,
+    /// port of node if present
+    pub port: Option<NodePort>,
+}
+
+/// Name of [`Node`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NodeName {
+    /// IPv4 or IPv6 address
+    Ip(IpAddr),
+    /// `unknown` node whose identity is not known
+    Unknown,
+    /// obfuscated identifier including leading `_` such as `_hidden`
+    Obfuscated(String),
+}
+
+/// Port of [`Node`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NodePort {
+    /// numeric port
+    Port(u16),
+    /// obfuscated port including leading `_` such as `_9`
+    Obfuscated(String),
+}
+
+impl FromStr for Node {
+    type Err = InvalidForwardedKind;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, port) = if let Some(rest) = value.strip_prefix('[') {
+            let (ip, rest) = rest
+                .split_once(']')
+                .ok_or(InvalidForwardedKind::InvalidNode)?;
+            let ip = ip
+                .parse::<Ipv6Addr>()
+                .map_err(|_| InvalidForwardedKind::InvalidNode)?;
+            let port = if rest.is_empty() {
+                None
+            } else {
+                Some(
+                    rest.strip_prefix(':')
+                        .ok_or(InvalidForwardedKind::InvalidNode)?,
+                )
+            };
+            (NodeName::Ip(IpAddr::V6(ip)), port)
+        } else {
+            let (name, port) = match value.split_once(':') {
+                Some((name, port)) => (name, Some(port)),
+                None => (value, None),
+            };
+            let name = if name.eq_ignore_ascii_case("unknown") {
+                NodeName::Unknown
+            } else if is_obfuscated(name) {
+                NodeName::Obfuscated(name.to_string())
+            } else {
+                let ip = name
+                    .parse::<Ipv4Addr>()
+                    .map_err(|_| InvalidForwardedKind::InvalidNode)?;
+                NodeName::Ip(IpAddr::V4(ip))
+            };
+            (name, port)
+        };
+        let port = port
+            .map(|port| {
+                if is_obfuscated(port) {
+                    Ok(NodePort::Obfuscated(port.to_string()))
+                } else if (1..=5).contains(&port.len()) && port.bytes().all(|b| b.is_ascii_digit())
+                {
+                    port.parse()
+                        .map(NodePort::Port)
+                        .map_err(|_| InvalidForwardedKind::InvalidNode)
+                } else {
+                    Err(InvalidForwardedKind::InvalidNode)
+                }
+            })
+            .transpose()?;
+        Ok(Self { name, port })
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            NodeName::Ip(IpAddr::V4(ip)) => write!(f, "{ip}")?,
+            NodeName::Ip(IpAddr::V6(ip)) => write!(f, "[{ip}]")?,
+            NodeName::Unknown => f.write_str("unknown")?,
+            NodeName::Obfuscated(name) => f.write_str(name)?,
+        }
+        match &self.port {
+            Some(NodePort::Port(port)) => write!(f, ":{port}"),
+            Some(NodePort::Obfuscated(port)) => write!(f, ":{port}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse value of single `Forwarded` header into typed elements
+///
+/// # Errors
+/// Returns error if value doesn't follow RFC 7239 grammar or `for`, `by` or
+/// `proto` parameter is invalid
+///
+/// # Example
+/// ```
+/// let elements = tower_allowed_hosts::forwarded::parse(
+///     r#"for="[2001:db8::1]:4711";proto=https;host=example.com"#,
+/// )?;
+/// assert_eq!(elements[0].host.as_deref(), Some("example.com"));
+/// # Ok::<(), tower_allowed_hosts::error::InvalidForwardedKind>(())
+/// ```
+pub fn parse(value: &str) -> Result<Vec<ForwardedElement>, InvalidForwardedKind> {
+    parse_pairs(value)?
+        .into_iter()
+        .map(ForwardedElement::from_pairs)
+        .collect()
+}
+
+/// Create iterator over elements of all `Forwarded` headers in order of
+/// appearance
+///
+/// Iterator yields error once and stops when header is invalid.
+///
+/// # Example
+/// ```
+/// use http::HeaderMap;
+/// use http::header::FORWARDED;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.append(FORWARDED, "for=192.0.2.43".parse().unwrap());
+/// headers.append(FORWARDED, "for=198.51.100.17, for=unknown".parse().unwrap());
+/// assert_eq!(
+///     tower_allowed_hosts::forwarded::elements(&headers).count(),
+///     3
+/// );
+/// ```
+#[must_use]
+pub fn elements(headers: &HeaderMap) -> Elements<'_> {
+    Elements {
+        headers: headers.get_all(FORWARDED).iter(),
+        current: Vec::new().into_iter(),
+        failed: false,
+    }
+}
+
+/// Iterator over elements of all `Forwarded` headers created by [`elements`]
+#[derive(Debug)]
+pub struct Elements<'a> {
+    headers: ValueIter<'a, HeaderValue>,
+    current: std::vec::IntoIter<ForwardedElement>,
+    failed: bool,
+}
+
+impl Iterator for Elements<'_> {
+    type Item = Result<ForwardedElement, InvalidForwardedKind>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            if let Some(element) = self.current.next() {
+                return Some(Ok(element));
+            }
+            let header = self.headers.next()?;
+            let parsed = std::str::from_utf8(header.as_bytes())
+                .map_err(|_| InvalidForwardedKind::InvalidEncoding)
+                .and_then(parse);
+            match parsed {
+                Ok(parsed) => self.current = parsed.into_iter(),
+                Err(err) => {
+                    self.failed = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+/// Serialize elements into value of `Forwarded` header
+///
+/// # Errors
+/// Returns error if value contains character which can't be part of header
+/// value such as control character
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::forwarded::{self, ForwardedElement};
+///
+/// let elements = forwarded::parse("for=192.0.2.43, host=\"a,b\"").unwrap();
+/// let value = forwarded::to_header_value(&elements).unwrap();
+/// assert_eq!(value, "for=192.0.2.43, host=\"a,b\"");
+/// ```
+pub fn to_header_value(elements: &[ForwardedElement]) -> Result<HeaderValue, InvalidHeaderValue> {
+    let value = elements
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::from_str(&value)
+}
+
+/// Parse value of `Forwarded` header according to RFC 7239 grammar
+///
+/// ```text
+/// Forwarded         = 1#forwarded-element
+/// forwarded-element = [ forwarded-pair ] *( ";" [ forwarded-pair ] )
+/// forwarded-pair    = token "=" value
+/// value             = token / quoted-string
+/// ```
+///
+/// Parameter name must not appear more than once in same element as required
+/// by RFC 7239. Quoted string is unescaped so `"a\"b"` is parsed as `a"b`.
+/// Empty list elements are ignored and optional whitespace is allowed around
+/// `,` and `;` but not around `=`.
+pub(crate) fn parse_pairs(value: &str) -> Result<Vec<Pairs>, InvalidForwardedKind> {
+    let mut parser = Parser {
+        input: value,
+        pos: 0,
+    };
+    let mut elements = Vec::new();
+    loop {
+        let pairs = parser.element()?;
+        if !pairs.is_empty() {
+            elements.push(pairs);
+        }
+        match parser.peek() {
+            None => return Ok(elements),
+            Some(b',') => parser.pos += 1,
+            Some(_) => return Err(InvalidForwardedKind::UnexpectedCharacter),
+        }
+    }
+}
+
+/// Cursor over `Forwarded` header value
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Peek byte at current position
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    /// Skip optional whitespace
+    fn skip_ows(&mut self) {
+        while let Some(b' ' | b'\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse single forwarded element up to next `,` or end of input
+    fn element(&mut self) -> Result<Pairs, InvalidForwardedKind> {
+        let mut pairs = Vec::new();
+        loop {
+            self.skip_ows();
+            match self.peek() {
+                None | Some(b',') => return Ok(pairs),
+                Some(b';') => {
+                    self.pos += 1;
+                    continue;
+                }
+                Some(_) => {}
+            }
+            let name = self.token();
+            if name.is_empty() {
+                return Err(InvalidForwardedKind::InvalidName);
+            }
+            if self.peek() != Some(b'=') {
+                return Err(InvalidForwardedKind::MissingValue);
+            }
+            self.pos += 1;
+            let value = if self.peek() == Some(b'"') {
+                self.quoted_string()?
+            } else {
+                let token = self.token();
+                if token.is_empty() {
+                    return Err(InvalidForwardedKind::InvalidValue);
+                }
+                token.to_string()
+            };
+            let name = name.to_ascii_lowercase();
+            if pairs.iter().any(|(existing, _)| *existing == name) {
+                return Err(InvalidForwardedKind::DuplicateParameter);
+            }
+            pairs.push((name, value));
+            self.skip_ows();
+            match self.peek() {
+                None | Some(b',') => return Ok(pairs),
+                Some(b';') => self.pos += 1,
+                Some(_) => return Err(InvalidForwardedKind::UnexpectedCharacter),
+            }
+        }
+    }
+
+    /// Parse token which may be empty
+    fn token(&mut self) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_tchar) {
+            self.pos += 1;
+        }
+        &self.input[start..self.pos]
+    }
+
+    /// Parse quoted string starting at `"` and return unescaped value
+    fn quoted_string(&mut self) -> Result<String, InvalidForwardedKind> {
+        self.pos += 1;
+        let mut value = String::new();
+        let mut chars = self.input[self.pos..].chars();
+        while let Some(c) = chars.next() {
+            self.pos += c.len_utf8();
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = chars
+                        .next()
+                        .ok_or(InvalidForwardedKind::UnterminatedQuote)?;
+                    self.pos += escaped.len_utf8();
+                    if escaped != '\t' && escaped.is_ascii_control() {
+                        return Err(InvalidForwardedKind::InvalidEscape);
+                    }
+                    value.push(escaped);
+                }
+                '\t' => value.push(c),
+                c if c.is_ascii_control() => {
+                    return Err(InvalidForwardedKind::UnexpectedCharacter);
+                }
+                c => value.push(c),
+            }
+        }
+        Err(InvalidForwardedKind::UnterminatedQuote)
+    }
+}
+
+/// Check whether byte is `tchar` of RFC 9110
+fn is_tchar(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric()
+        || matches!(
+            byte,
+            b'!' | b'#'
+                | b'$'
+                | b'%'
+                | b'&'
+                | b'\''
+                | b'*'
+                | b'+'
+                | b'-'
+                | b'.'
+                | b'^'
+                | b'_'
+                | b'`'
+                | b'|'
+                | b'~'
+        )
+}
+
+/// Serialize parameters as `forwarded-pair` joined by `;` in provided order
+#[cfg(feature = "signed")]
+pub(crate) fn write_pairs<'a, I>(pairs: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let mut output = String::new();
+    for (index, (name, value)) in pairs.into_iter().enumerate() {
+        if index > 0 {
+            output.push(';');
+        }
+        output.push_str(name);
+        output.push('=');
+        // writing to string never fails
+        let _ = write_value(&mut output, value);
+    }
+    output
+}
+
+/// Write value as token if possible else as quoted string
+fn write_value<W>(f: &mut W, value: &str) -> fmt::Result
+where
+    W: Write,
+{
+    if !value.is_empty() && value.bytes().all(is_tchar) {
+        return f.write_str(value);
+    }
+    f.write_char('"')?;
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char('"')
+}
+
+/// Check whether value is obfuscated node name or port of RFC 7239
+fn is_obfuscated(value: &str) -> bool {
+    value.strip_prefix('_').is_some_and(|id| {
+        !id.is_empty()
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+    })
+}
+
+/// Check whether value is URI scheme of RFC 3986
+fn is_scheme(value: &str) -> bool {
+    value
+        .bytes()
+        .next()
+        .is_some_and(|b| b.is_ascii_alphabetic())
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use http::HeaderMap;
+    use http::header::FORWARDED;
+
+    use crate::error::InvalidForwardedKind;
+    use crate::forwarded::{
+        ForwardedElement, Node, NodeName, NodePort, elements, parse, parse_pairs, to_header_value,
+    };
+
+    fn pairs(values: &[&[(&str, &str)]]) -> Vec<Vec<(String, String)>> {
+        values
+            .iter()
+            .map(|element| {
+                element
+                    .iter()
+                    .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rfc_examples() {
+        assert_eq!(
+            parse_pairs(r#"for="_gazonk""#),
+            Ok(pairs(&[&[("for", "_gazonk")]]))
+        );
+        assert_eq!(
+            parse_pairs(r#"For="[2001:db8:cafe::17]:4711""#),
+            Ok(pairs(&[&[("for", "[2001:db8:cafe::17]:4711")]]))
+        );
+        assert_eq!(
+            parse_pairs("for=192.0.2.60;proto=http;by=203.0.113.43"),
+            Ok(pairs(&[&[
+                ("for", "192.0.2.60"),
+                ("proto", "http"),
+                ("by", "203.0.113.43")
+            ]]))
+        );
+        assert_eq!(
+            parse_pairs("for=192.0.2.43, for=198.51.100.17"),
+            Ok(pairs(&[
+                &[("for", "192.0.2.43")],
+                &[("for", "198.51.100.17")]
+            ]))
+        );
+        assert_eq!(
+            parse_pairs(r#"for=192.0.2.43,for="[2001:db8:cafe::17]",for=unknown"#),
+            Ok(pairs(&[
+                &[("for", "192.0.2.43")],
+                &[("for", "[2001:db8:cafe::17]")],
+                &[("for", "unknown")]
+            ]))
+        );
+        assert_eq!(
+            parse_pairs(
+                "for=192.0.2.43, for=198.51.100.17;by=203.0.113.60;proto=http;host=example.com"
+            ),
+            Ok(pairs(&[
+                &[("for", "192.0.2.43")],
+                &[
+                    ("for", "198.51.100.17"),
+                    ("by", "203.0.113.60"),
+                    ("proto", "http"),
+                    ("host", "example.com")
+                ]
+            ]))
+        );
+    }
+
+    #[test]
+    fn quoted_value() {
+        assert_eq!(
+            parse_pairs(r#"for="[2001:db8::1]:4711";host="a,b""#),
+            Ok(pairs(&[&[("for", "[2001:db8::1]:4711"), ("host", "a,b")]]))
+        );
+        assert_eq!(
+            parse_pairs(r#"host="a\"b;c\\d""#),
+            Ok(pairs(&[&[("host", r#"a"b;c\d"#)]]))
+        );
+        assert_eq!(parse_pairs(r#"host="""#), Ok(pairs(&[&[("host", "")]])));
+    }
+
+    #[test]
+    fn empty_elements_and_whitespace() {
+        assert_eq!(parse_pairs(""), Ok(vec![]));
+        assert_eq!(
+            parse_pairs(" , for=a ; by=b ;, ,for=c"),
+            Ok(pairs(&[&[("for", "a"), ("by", "b")], &[("for", "c")]]))
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(parse_pairs("for"), Err(InvalidForwardedKind::MissingValue));
+        assert_eq!(
+            parse_pairs("for =a"),
+            Err(InvalidForwardedKind::MissingValue)
+        );
+        assert_eq!(parse_pairs("for="), Err(InvalidForwardedKind::InvalidValue));
+        assert_eq!(
+            parse_pairs("for= a"),
+            Err(InvalidForwardedKind::InvalidValue)
+        );
+        assert_eq!(
+            parse_pairs("for=[::1]"),
+            Err(InvalidForwardedKind::InvalidValue)
+        );
+        assert_eq!(parse_pairs("=a"), Err(InvalidForwardedKind::InvalidName));
+        assert_eq!(
+            parse_pairs("for=a b"),
+            Err(InvalidForwardedKind::UnexpectedCharacter)
+        );
+        assert_eq!(
+            parse_pairs(r#"for="a"b"#),
+            Err(InvalidForwardedKind::UnexpectedCharacter)
+        );
+        assert_eq!(
+            parse_pairs("for=\"a\u{1}\""),
+            Err(InvalidForwardedKind::UnexpectedCharacter)
+        );
+        assert_eq!(
+            parse_pairs(r#"for="abc"#),
+            Err(InvalidForwardedKind::UnterminatedQuote)
+        );
+        assert_eq!(
+            parse_pairs(r#"for="abc\"#),
+            Err(InvalidForwardedKind::UnterminatedQuote)
+        );
+        assert_eq!(
+            parse_pairs("for=\"a\\\u{0}\""),
+            Err(InvalidForwardedKind::InvalidEscape)
+        );
+        assert_eq!(
+            parse_pairs("for=a;host=b;For=c"),
+            Err(InvalidForwardedKind::DuplicateParameter)
+        );
+        assert_eq!(
+            parse_pairs("host=a;host=\"a\""),
+            Err(InvalidForwardedKind::DuplicateParameter)
+        );
+        assert!(parse_pairs("host=a,host=b").is_ok());
+    }
+
+    #[test]
+    fn parse_node() {
+        assert_eq!(
+            "192.0.2.43:80".parse::<Node>(),
+            Ok(Node {
+                name: NodeName::Ip(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 43))),
+                port: Some(NodePort::Port(80)),
+            })
+        );
+        assert_eq!(
+            "[2001:db8:cafe::17]:_port".parse::<Node>(),
+            Ok(Node {
+                name: NodeName::Ip(IpAddr::V6(Ipv6Addr::new(
+                    0x2001, 0xdb8, 0xcafe, 0, 0, 0, 0, 0x17
+                ))),
+                port: Some(NodePort::Obfuscated("_port".to_string())),
+            })
+        );
+        assert_eq!(
+            "unknown".parse::<Node>(),
+            Ok(Node {
+                name: NodeName::Unknown,
+                port: None,
+            })
+        );
+        assert_eq!(
+            "_gazonk".parse::<Node>(),
+            Ok(Node {
+                name: NodeName::Obfuscated("_gazonk".to_string()),
+                port: None,
+            })
+        );
+        for invalid in [
+            "",
+            "example.com",
+            "2001:db8::1",
+            "[2001:db8::1",
+            "[2001:db8::1]80",
+            "192.0.2.43:",
+            "192.0.2.43:65536",
+            "192.0.2.43:123456",
+            "_",
+            "_a b",
+        ] {
+            assert_eq!(
+                invalid.parse::<Node>(),
+                Err(InvalidForwardedKind::InvalidNode),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_element() {
+        assert_eq!(
+            parse(
+                r#"for="[2001:db8:cafe::17]:4711";by=unknown;host=example.com;proto=https;secret=a"#
+            ),
+            Ok(vec![ForwardedElement {
+                r#for: Some("[2001:db8:cafe::17]:4711".parse().unwrap()),
+                by: Some("unknown".parse().unwrap()),
+                host: Some("example.com".to_string()),
+                proto: Some("https".to_string()),
+                extensions: vec![("secret".to_string(), "a".to_string())],
+            }])
+        );
+        assert_eq!(parse("for=a"), Err(InvalidForwardedKind::InvalidNode));
+        assert_eq!(
+            parse("proto=1http"),
+            Err(InvalidForwardedKind::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn write_element() {
+        let value =
+            r#"for="[2001:db8:cafe::17]:4711";by=_hidden;host="a,b";proto=https;note="a\"b""#;
+        let parsed = parse(value).unwrap();
+        assert_eq!(parsed[0].to_string(), value);
+        assert_eq!(parse(&parsed[0].to_string()).unwrap(), parsed);
+
+        let multiple = parse("for=192.0.2.43,for=unknown;host=example.com").unwrap();
+        assert_eq!(
+            to_header_value(&multiple).unwrap(),
+            "for=192.0.2.43, for=unknown;host=example.com"
+        );
+        assert_eq!(
+            ForwardedElement {
+                host: Some(String::new()),
+                ..ForwardedElement::default()
+            }
+            .to_string(),
+            r#"host="""#
+        );
+        assert!(
+            to_header_value(&[ForwardedElement {
+                host: Some("a\nb".to_string()),
+                ..ForwardedElement::default()
+            }])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn iterate_elements() {
+        let mut headers = HeaderMap::new();
+        headers.append(FORWARDED, "for=192.0.2.43".parse().unwrap());
+        headers.append(FORWARDED, "for=unknown, by=_proxy".parse().unwrap());
+        let parsed = elements(&headers).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[2].by, Some("_proxy".parse().unwrap()));
+
+        headers.append(FORWARDED, "for=invalid".parse().unwrap());
+        headers.append(FORWARDED, "for=unknown".parse().unwrap());
+        let mut iter = elements(&headers).skip(3);
+        assert_eq!(iter.next(), Some(Err(InvalidForwardedKind::InvalidNode)));
+        assert_eq!(iter.next(), None);
+    }
+}
@@ -0,0 +1,323 @@
+use unicode_security::{RestrictionLevel, RestrictionLevelDetection as _, skeleton};
+
+use crate::error::{Error, InvalidHostKind};
+use crate::host::split_port;
+
+/// Base of punycode encoding
+const BASE: u32 = 36;
+/// Minimum threshold of punycode encoding
+const T_MIN: u32 = 1;
+/// Maximum threshold of punycode encoding
+const T_MAX: u32 = 26;
+/// Skew used while adapting bias
+const SKEW: u32 = 38;
+/// Damp used while adapting bias for first time
+const DAMP: u32 = 700;
+/// Initial bias of punycode encoding
+const INITIAL_BIAS: u32 = 72;
+/// Initial code point of punycode encoding
+const INITIAL_N: u32 = 128;
+
+/// Adapt bias after decoding each code point
+fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+/// Decode punycode value without `xn--` prefix according to RFC 3492
+fn decode_punycode(input: &str) -> Option<String> {
+    let (basic, extended) = input.rsplit_once('-').unwrap_or(("", input));
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut output = basic.chars().collect::<Vec<_>>();
+    let (mut n, mut i, mut bias) = (INITIAL_N, 0_u32, INITIAL_BIAS);
+    let mut bytes = extended.bytes().peekable();
+    while bytes.peek().is_some() {
+        let old_i = i;
+        let mut weight = 1_u32;
+        let mut k = BASE;
+        loop {
+            let digit = match bytes.next()? {
+                byte @ b'a'..=b'z' => byte - b'a',
+                byte @ b'A'..=b'Z' => byte - b'A',
+                byte @ b'0'..=b'9' => byte - b'0' + 26,
+                _ => return None,
+            };
+            let digit = u32::from(digit);
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = if k <= bias {
+                T_MIN
+            } else if k >= bias + T_MAX {
+                T_MAX
+            } else {
+                k - bias
+            };
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(BASE - threshold)?;
+            k += BASE;
+        }
+        let length = u32::try_from(output.len() + 1).ok()?;
+        bias = adapt(i - old_i, length, old_i == 0);
+        n = n.checked_add(i / length)?;
+        i %= length;
+        output.insert(usize::try_from(i).ok()?, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+/// Convert host to its unicode form by decoding all `xn--` labels
+///
+/// Port is removed from host. Returns `None` if any label contains invalid
+/// punycode.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::homograph::to_unicode;
+///
+/// assert_eq!(
+///     to_unicode("xn--mnchen-3ya.de:443").as_deref(),
+///     Some("münchen.de")
+/// );
+/// ```
+#[must_use]
+pub fn to_unicode(host: &str) -> Option<String> {
+    let (hostname, _) = split_port(host);
+    let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+    let labels = hostname
+        .split('.')
+        .map(|label| {
+            match label.get(..4) {
+                Some(prefix) if prefix.eq_ignore_ascii_case("xn--") => {
+                    decode_punycode(&label[4..]).map(|label| label.to_lowercase())
+                }
+                _ => Some(label.to_lowercase()),
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(labels.join("."))
+}
+
+/// Return confusable skeleton of unicode host
+fn host_skeleton(host: &str) -> String {
+    skeleton(&host.to_lowercase()).collect()
+}
+
+/// Homograph check which rejects internationalized host that can be confused
+/// with other host according to UTS-39 rules
+///
+/// Only host containing non ascii character after decoding `xn--` labels is
+/// checked. Such host is rejected when
+/// - any label mixes scripts and isn't at least highly restrictive as defined
+///   by UTS-39 such as Cyrillic `а` in `exаmple.com`
+/// - host has same confusable skeleton as one of protected host while being
+///   different from it
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::homograph::HomographCheck;
+///
+/// let check = HomographCheck::new().with_protected_hosts(["paypal.com"]);
+/// assert!(check.check("example.com").is_ok());
+/// assert!(check.check("xn--exmple-4nf.com").is_err());
+/// assert!(check.check("xn--l-7sba6dbr.com").is_err());
+/// ```
+#[derive(Clone)]
+pub struct HomographCheck {
+    reject_mixed_script: bool,
+    protected: Vec<(String, String)>,
+}
+
+impl Default for HomographCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HomographCheck {
+    /// Create new homograph check which rejects mixed script host
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            reject_mixed_script: true,
+            protected: Vec::new(),
+        }
+    }
+
+    /// Set whether host containing label with mixed scripts is rejected
+    #[must_use]
+    pub fn reject_mixed_script(self, reject_mixed_script: bool) -> Self {
+        Self {
+            reject_mixed_script,
+            ..self
+        }
+    }
+
+    /// Add hosts which are protected from confusable hosts
+    ///
+    /// Usually these are same hosts which are allowed by host matcher
+    #[must_use]
+    pub fn with_protected_hosts<I>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for host in hosts {
+            let host = to_unicode(host.as_ref()).unwrap_or_else(|| host.as_ref().to_lowercase());
+            self.protected.push((host_skeleton(&host), host));
+        }
+        self
+    }
+
+    /// Check host for homograph
+    ///
+    /// # Errors
+    /// Returns [`Error::ConfusableHost`] if host mixes scripts or is confusable
+    /// with protected host and [`Error::InvalidHost`] if host contains invalid
+    /// punycode
+    pub fn check(&self, host: &str) -> Result<(), Error> {
+        let unicode_host =
+            to_unicode(host).ok_or(Error::InvalidHost(InvalidHostKind::InvalidPunycode))?;
+        if unicode_host.is_ascii() {
+            return Ok(());
+        }
+        if self.reject_mixed_script
+            && unicode_host
+                .split('.')
+                .any(|label| !label.check_restriction_level(RestrictionLevel::HighlyRestrictive))
+        {
+            return Err(Error::ConfusableHost(host.to_string()));
+        }
+        if !self.protected.is_empty() {
+            let host_skeleton = host_skeleton(&unicode_host);
+            if self.protected.iter().any(|(skeleton, protected)| {
+                *skeleton == host_skeleton && *protected != unicode_host
+            }) {
+                return Err(Error::ConfusableHost(host.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::homograph::{HomographCheck, decode_punycode, to_unicode};
+
+    #[test]
+    fn punycode_decode() {
+        assert_eq!(decode_punycode("mnchen-3ya").as_deref(), Some("münchen"));
+        assert_eq!(decode_punycode("e1afmkfd").as_deref(), Some("пример"));
+        assert_eq!(decode_punycode("wgv71a119e").as_deref(), Some("日本語"));
+        assert_eq!(decode_punycode("exmple-4nf").as_deref(), Some("exаmple"));
+    }
+
+    #[test]
+    fn punycode_rfc3492_vectors() {
+        // sample strings of RFC 3492 section 7.1
+        for (encoded, decoded) in [
+            ("egbpdaj6bu4bxfgehfvwxn", "ليهمابتكلموشعربي؟"),
+            ("ihqwcrb4cv8a8dqg056pqjye", "他们为什么不说中文"),
+            ("ihqwctvzc91f659drss3x8bo0yb", "他們爲什麽不說中文"),
+            ("Proprostnemluvesky-uyb24dma41a", "Pročprostěnemluvíčesky"),
+            ("4dbcagdahymbxekheh6e0a7fei0b", "למההםפשוטלאמדבריםעברית"),
+            (
+                "b1abfaaepdrnnbgefbadotcwatmq2g4l",
+                "почемужеонинеговорятпорусски",
+            ),
+            (
+                "PorqunopuedensimplementehablarenEspaol-fmd56a",
+                "PorquénopuedensimplementehablarenEspañol",
+            ),
+            (
+                "TisaohkhngthchnitingVit-kjcr8268qyxafd2f1b9g",
+                "TạisaohọkhôngthểchỉnóitiếngViệt",
+            ),
+            ("3B-ww4c5e180e575a65lsy2b", "3年B組金八先生"),
+            (
+                "-with-SUPER-MONKEYS-pc58ag80a8qai00g7n9n",
+                "安室奈美恵-with-SUPER-MONKEYS",
+            ),
+            (
+                "Hello-Another-Way--fc4qua05auwb3674vfr0b",
+                "Hello-Another-Way-それぞれの場所",
+            ),
+            ("2-u9tlzr9756bt3uc0v", "ひとつ屋根の下2"),
+            ("MajiKoi5-783gue6qz075azm5e", "MajiでKoiする5秒前"),
+            ("de-jg4avhby1noc0d", "パフィーdeルンバ"),
+            ("d9juau41awczczp", "そのスピードで"),
+            ("-> $1.00 <--", "-> $1.00 <-"),
+        ] {
+            assert_eq!(
+                decode_punycode(encoded).as_deref(),
+                Some(decoded),
+                "{encoded}"
+            );
+        }
+        assert_eq!(decode_punycode("MNCHEN-3YA").as_deref(), Some("MüNCHEN"));
+    }
+
+    #[test]
+    fn punycode_invalid() {
+        // overflow of delta
+        assert_eq!(decode_punycode("99999999999"), None);
+        // code point larger than U+10FFFF
+        assert_eq!(decode_punycode("a-99999a"), None);
+        // invalid digit
+        assert_eq!(decode_punycode("a!"), None);
+        assert_eq!(decode_punycode("a-!"), None);
+        assert_eq!(decode_punycode("mnchen-3yü"), None);
+        // non ascii basic code point
+        assert_eq!(decode_punycode("ü-3ya"), None);
+        // truncated variable length integer
+        assert_eq!(decode_punycode("mnchen-3y"), None);
+    }
+
+    #[test]
+    fn host_to_unicode() {
+        assert_eq!(
+            to_unicode("www.XN--MNCHEN-3YA.de.").as_deref(),
+            Some("www.münchen.de")
+        );
+        assert_eq!(to_unicode("example.com:80").as_deref(), Some("example.com"));
+        assert_eq!(to_unicode("xn--a!.com"), None);
+    }
+
+    #[test]
+    fn mixed_script_host() {
+        let check = HomographCheck::new();
+        assert!(check.check("example.com").is_ok());
+        assert!(check.check("xn--mnchen-3ya.de").is_ok());
+        assert!(check.check("xn--e1afmkfd.xn--p1ai").is_ok());
+        assert!(check.check("xn--wgv71a119e.jp").is_ok());
+        assert!(check.check("xn--exmple-4nf.com").is_err());
+        assert!(check.check("exаmple.com").is_err());
+        assert!(
+            HomographCheck::new()
+                .reject_mixed_script(false)
+                .check("xn--exmple-4nf.com")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn confusable_host() {
+        let check = HomographCheck::new()
+            .reject_mixed_script(false)
+            .with_protected_hosts(["example.com", "xn--mnchen-3ya.de"]);
+        assert!(check.check("example.com").is_ok());
+        assert!(check.check("xn--mnchen-3ya.de").is_ok());
+        assert!(check.check("xn--exmple-4nf.com").is_err());
+        assert!(check.check("xn--xmpl-53d2af.com").is_err());
+        assert!(check.check("xn--mnchen-3ya.com").is_ok());
+    }
+}
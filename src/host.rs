@@ -0,0 +1,202 @@
+use std::borrow::Cow;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::error::InvalidHostKind;
+
+/// Maximum length of host name excluding trailing dot
+const MAX_HOST_LENGTH: usize = 253;
+/// Maximum length of single label of host name
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Split host into hostname and port
+///
+/// IP literal such as `[::1]:8080` keeps its brackets in hostname. Value
+/// containing multiple `:` without brackets is returned as hostname without
+/// port.
+pub(crate) fn split_port(host: &str) -> (&str, Option<&str>) {
+    if host.starts_with('[') {
+        return match host.rfind(']') {
+            Some(end) if host[end + 1..].starts_with(':') => {
+                (&host[..=end], Some(&host[end + 2..]))
+            }
+            _ => (host, None),
+        };
+    }
+    match host.split_once(':') {
+        Some((hostname, port)) if !port.contains(':') => (hostname, Some(port)),
+        _ => (host, None),
+    }
+}
+
+/// Convert value to ascii lowercase only allocating when value contains
+/// uppercase character
+pub(crate) fn to_lowercase(value: &str) -> Cow<'_, str> {
+    if value.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(value.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Check whether two hosts are equivalent ignoring case of hostname
+///
+/// Missing or empty port is considered same as provided default port.
+pub(crate) fn equivalent(left: &str, right: &str, default_port: Option<&str>) -> bool {
+    let (left_hostname, left_port) = split_port(left);
+    let (right_hostname, right_port) = split_port(right);
+    let left_port = left_port.filter(|port| !port.is_empty()).or(default_port);
+    let right_port = right_port.filter(|port| !port.is_empty()).or(default_port);
+    left_hostname.eq_ignore_ascii_case(right_hostname) && left_port == right_port
+}
+
+/// Strip port and trailing dot from host and return hostname
+pub(crate) fn hostname(host: &str) -> &str {
+    let (hostname, _) = split_port(host);
+    hostname.strip_suffix('.').unwrap_or(hostname)
+}
+
+/// Validate host strictly according to RFC 3986 host grammar
+///
+/// Host must be IP literal, IPv4 address or registered name optionally followed
+/// by numeric port. Registered name is limited to unreserved characters so
+/// percent encoding and sub delimiters are rejected. Labels must not be empty
+/// or longer than 63 characters and host name must not be longer than 253
+/// characters.
+pub(crate) fn validate(host: &str) -> Result<(), InvalidHostKind> {
+    if host.is_empty() {
+        return Err(InvalidHostKind::Empty);
+    }
+    let (hostname, port) = split_port(host);
+    if let Some(port) = port {
+        validate_port(port)?;
+    }
+    if let Some(ip_literal) = hostname.strip_prefix('[') {
+        return ip_literal
+            .strip_suffix(']')
+            .and_then(|ip| ip.parse::<Ipv6Addr>().ok())
+            .map(|_| ())
+            .ok_or(InvalidHostKind::InvalidIpAddress);
+    }
+    let name = hostname.strip_suffix('.').unwrap_or(hostname);
+    if name.is_empty() {
+        return Err(InvalidHostKind::Empty);
+    }
+    if name.len() > MAX_HOST_LENGTH {
+        return Err(InvalidHostKind::HostTooLong);
+    }
+    if let Some(byte) = name
+        .bytes()
+        .find(|b| !(b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')))
+    {
+        return Err(if byte == b'%' {
+            InvalidHostKind::PercentEncoded
+        } else {
+            InvalidHostKind::InvalidCharacter
+        });
+    }
+    if name.bytes().all(|b| b.is_ascii_digit() || b == b'.') && name.parse::<Ipv4Addr>().is_err() {
+        return Err(InvalidHostKind::InvalidIpAddress);
+    }
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err(InvalidHostKind::EmptyLabel);
+        }
+        if label.len() > MAX_LABEL_LENGTH {
+            return Err(InvalidHostKind::LabelTooLong);
+        }
+    }
+    Ok(())
+}
+
+/// Validate port is number between 0 and 65535 written with ascii digits
+pub(crate) fn validate_port(port: &str) -> Result<(), InvalidHostKind> {
+    if port.is_empty()
+        || port.len() > 5
+        || !port.bytes().all(|b| b.is_ascii_digit())
+        || port.parse::<u16>().is_err()
+    {
+        return Err(InvalidHostKind::InvalidPort);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::InvalidHostKind;
+    use crate::host::{equivalent, split_port, validate};
+
+    #[test]
+    fn split_port_of_host() {
+        assert_eq!(split_port("example.com"), ("example.com", None));
+        assert_eq!(
+            split_port("example.com:8080"),
+            ("example.com", Some("8080"))
+        );
+        assert_eq!(split_port("[::1]:443"), ("[::1]", Some("443")));
+        assert_eq!(split_port("[::1]"), ("[::1]", None));
+        assert_eq!(split_port("::1"), ("::1", None));
+    }
+
+    #[cfg(feature = "psl")]
+    #[test]
+    fn hostname_without_port_and_trailing_dot() {
+        use crate::host::hostname;
+
+        assert_eq!(hostname("example.com.:80"), "example.com");
+        assert_eq!(hostname("example.com"), "example.com");
+    }
+
+    #[test]
+    fn equivalent_host() {
+        assert!(equivalent("Example.COM", "example.com", None));
+        assert!(equivalent("example.com:443", "example.com", Some("443")));
+        assert!(equivalent("example.com:", "example.com:80", Some("80")));
+        assert!(equivalent("[::1]", "[::1]:443", Some("443")));
+        assert!(!equivalent("example.com:443", "example.com", None));
+        assert!(!equivalent("example.com:8443", "example.com", Some("443")));
+        assert!(!equivalent("example.com", "example.net", Some("443")));
+    }
+
+    #[test]
+    fn validate_host() {
+        assert_eq!(validate("example.com"), Ok(()));
+        assert_eq!(validate("example.com."), Ok(()));
+        assert_eq!(validate("sub_domain.example.com:8080"), Ok(()));
+        assert_eq!(validate("127.0.0.1:65535"), Ok(()));
+        assert_eq!(validate("[::1]:443"), Ok(()));
+        assert_eq!(validate("localhost"), Ok(()));
+        assert_eq!(validate(""), Err(InvalidHostKind::Empty));
+        assert_eq!(validate(":80"), Err(InvalidHostKind::Empty));
+        assert_eq!(
+            validate("evil.com/foo"),
+            Err(InvalidHostKind::InvalidCharacter)
+        );
+        assert_eq!(
+            validate("user@host"),
+            Err(InvalidHostKind::InvalidCharacter)
+        );
+        assert_eq!(
+            validate("host:80:80"),
+            Err(InvalidHostKind::InvalidCharacter)
+        );
+        assert_eq!(validate("host:65536"), Err(InvalidHostKind::InvalidPort));
+        assert_eq!(validate("host:"), Err(InvalidHostKind::InvalidPort));
+        assert_eq!(validate("host:+80"), Err(InvalidHostKind::InvalidPort));
+        assert_eq!(validate("%2e.com"), Err(InvalidHostKind::PercentEncoded));
+        assert_eq!(validate("a..com"), Err(InvalidHostKind::EmptyLabel));
+        assert_eq!(
+            validate("127.0.0.256"),
+            Err(InvalidHostKind::InvalidIpAddress)
+        );
+        assert_eq!(validate("[::g]"), Err(InvalidHostKind::InvalidIpAddress));
+        assert_eq!(validate("[v1.fe]"), Err(InvalidHostKind::InvalidIpAddress));
+        assert_eq!(
+            validate(&format!("{}.com", "a".repeat(64))),
+            Err(InvalidHostKind::LabelTooLong)
+        );
+        assert_eq!(
+            validate(&"a".repeat(5000)),
+            Err(InvalidHostKind::HostTooLong)
+        );
+    }
+}
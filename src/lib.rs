@@ -11,6 +11,8 @@
 //! information
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(test)]
+use criterion as _;
 #[doc(inline)]
 pub use error::Error;
 #[doc(inline)]
@@ -22,9 +24,26 @@ use crate::error::HostRejection;
 /// module for error
 pub mod error;
 
+/// module for parsing and writing forwarded header
+pub mod forwarded;
+
+/// module for homograph detection of internationalized host
+#[cfg(feature = "homograph")]
+pub mod homograph;
+
+/// module for host helpers
+mod host;
+
 /// module for matcher
 pub mod matcher;
 
+/// module for trusted proxies
+pub mod proxy;
+
+/// module for public suffix list
+#[cfg(feature = "psl")]
+pub mod psl;
+
 /// module for layer, service and future
 pub mod service;
 
@@ -40,6 +59,36 @@ mod tests;
 #[derive(Clone)]
 pub struct Host(pub String);
 
+/// Source from which value of [`Host`] is resolved
+///
+/// This enum is added as a extension to request along with [`Host`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HostSource {
+    /// host parameter of trusted `Forwarded` header
+    Forwarded,
+    /// trusted `X-Forwarded-Host` header
+    XForwarded,
+    /// `:authority` pseudo header or authority of request target
+    Authority,
+    /// `Host` header
+    Host,
+    /// configured default host for request without `Host` header
+    Default,
+}
+
+impl std::fmt::Display for HostSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Forwarded => write!(f, "forwarded"),
+            Self::XForwarded => write!(f, "x-forwarded-host"),
+            Self::Authority => write!(f, "authority"),
+            Self::Host => write!(f, "host"),
+            Self::Default => write!(f, "default host"),
+        }
+    }
+}
+
 #[cfg(feature = "axum")]
 impl<S> axum::extract::FromRequestParts<S> for Host
 where
@@ -1,10 +1,57 @@
-use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
 
-#[cfg(feature = "regex")]
-use regex::Regex;
 #[cfg(feature = "wildcard")]
 use wildmatch::WildMatchPattern;
 
+#[doc(inline)]
+pub use self::forwarded_chain::{EachElement, ForwardedChainMatcher};
+#[cfg(feature = "regex")]
+#[doc(inline)]
+pub use self::full_regex::{FullRegex, is_anchored};
+#[doc(inline)]
+pub use self::host_glob::HostGlob;
+#[doc(inline)]
+pub use self::host_set::HostSet;
+#[doc(inline)]
+pub use self::host_trie::HostTrie;
+#[cfg(feature = "regex")]
+#[doc(inline)]
+pub use self::regex_hosts::RegexHosts;
+#[doc(inline)]
+pub use self::rule_set::{Action, Precedence, RuleSet};
+#[cfg(feature = "signed")]
+#[doc(inline)]
+pub use self::signed_forwarded::SignedForwarded;
+use crate::forwarded::Parameters;
+
+/// module for forwarded chain matcher
+mod forwarded_chain;
+
+/// module for DNS label aware glob matcher
+mod host_glob;
+
+/// module for host set matcher
+mod host_set;
+
+/// module for reversed label trie matcher
+mod host_trie;
+
+/// module for ordered allow and deny rules
+mod rule_set;
+
+/// module for full regex matcher
+#[cfg(feature = "regex")]
+mod full_regex;
+
+/// module for regex set matcher
+#[cfg(feature = "regex")]
+mod regex_hosts;
+
+/// module for HMAC signed forwarded matcher
+#[cfg(feature = "signed")]
+mod signed_forwarded;
+
 /// Trait for matching against the value
 ///
 /// A `Matcher` is responsible for checking whether a given value is consider to
@@ -12,6 +59,57 @@ use wildmatch::WildMatchPattern;
 pub trait Matcher {
     /// Checks if provided value matches according to matcher
     fn matches_value(&self, value: &str) -> bool;
+
+    /// Checks provided value and returns decision along with rule which
+    /// decided it
+    ///
+    /// Default implementation uses [`Matcher::matches_value`] without any rule.
+    /// Matcher such as [`RuleSet`] overrides it so deciding rule is reported
+    /// in rejection error. Combinators and collections propagate decision of
+    /// inner matcher which decided outcome.
+    fn decide(&self, value: &str) -> Decision {
+        Decision::new(self.matches_value(value))
+    }
+    /// Checks configuration of matcher once when layer is created
+    ///
+    /// Default implementation does nothing. Implementation for `regex::Regex`
+    /// logs warning with `tracing` feature when its pattern is not anchored.
+    /// Combinators and collections forward it to inner matchers so check
+    /// doesn't need to run for each request.
+    fn check_config(&self) {}
+}
+
+/// Decision made by [`Matcher`] for a value
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Decision {
+    /// Whether value is allowed
+    pub allowed: bool,
+    /// Description of rule which decided outcome if known
+    pub rule: Option<Arc<str>>,
+}
+
+impl Decision {
+    /// Create new decision without rule
+    #[must_use]
+    pub fn new(allowed: bool) -> Self {
+        Self {
+            allowed,
+            rule: None,
+        }
+    }
+
+    /// Attach rule which decided outcome
+    #[must_use]
+    pub fn with_rule<T>(self, rule: T) -> Self
+    where
+        T: Into<Arc<str>>,
+    {
+        Self {
+            rule: Some(rule.into()),
+            ..self
+        }
+    }
 }
 
 /// Trait for matching the presence and values of parameters in a `Forwarded`
@@ -21,13 +119,134 @@ pub trait Matcher {
 /// only consider the `host=` value from a `Forwarded` header if the matcher
 /// determines that the header’s parameters are acceptable.
 ///
-/// The matcher receives a map of all key–value pairs in the `Forwarded` entry
-/// (e.g. `for=...;by=...;host=...;token=value`)
+/// The matcher receives an ordered view of all key–value pairs in the
+/// `Forwarded` entry (e.g. `for=...;by=...;host=...;token=value`). Entry
+/// containing duplicate key is rejected before it reaches matcher.
 pub trait KeyValueMatcher {
     /// Checks if provided value matches according to matcher
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool;
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool;
 }
 
+/// Extension trait which provides combinator methods for [`Matcher`]
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{Matcher as _, MatcherExt as _};
+///
+/// let matcher = "a.com".or("b.com").and("b.com".not());
+/// assert!(matcher.matches_value("a.com"));
+/// assert!(!matcher.matches_value("b.com"));
+/// ```
+pub trait MatcherExt: Matcher {
+    /// Combine matcher with other matcher which matches only when both matches
+    fn and<M>(self, other: M) -> And<Self, M>
+    where
+        Self: Sized,
+        M: Matcher,
+    {
+        And::new(self, other)
+    }
+
+    /// Combine matcher with other matcher which matches when either matches
+    fn or<M>(self, other: M) -> Or<Self, M>
+    where
+        Self: Sized,
+        M: Matcher,
+    {
+        Or::new(self, other)
+    }
+
+    /// Combine matcher with other matcher which matches when exactly one of
+    /// them matches
+    fn xor<M>(self, other: M) -> Xor<Self, M>
+    where
+        Self: Sized,
+        M: Matcher,
+    {
+        Xor::new(self, other)
+    }
+
+    /// Negate matcher
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not::new(self)
+    }
+
+    /// Attach label to matcher which is used for diagnostics
+    fn with_label<T>(self, label: T) -> Labeled<Self>
+    where
+        Self: Sized,
+        T: Into<String>,
+    {
+        Labeled::new(label, self)
+    }
+}
+
+impl<M> MatcherExt for M where M: Matcher + ?Sized {}
+
+/// Extension trait which provides combinator methods for [`KeyValueMatcher`]
+///
+/// Type which implements both [`Matcher`] and [`KeyValueMatcher`] such as
+/// [`Any`] needs fully qualified syntax if both extension traits are in scope.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::KeyValueMatcherExt as _;
+///
+/// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+///     .with_forwarded_matcher(("by", "proxy1").or(("by", "proxy2")));
+/// ```
+pub trait KeyValueMatcherExt: KeyValueMatcher {
+    /// Combine matcher with other matcher which matches only when both matches
+    fn and<M>(self, other: M) -> And<Self, M>
+    where
+        Self: Sized,
+        M: KeyValueMatcher,
+    {
+        And::new(self, other)
+    }
+
+    /// Combine matcher with other matcher which matches when either matches
+    fn or<M>(self, other: M) -> Or<Self, M>
+    where
+        Self: Sized,
+        M: KeyValueMatcher,
+    {
+        Or::new(self, other)
+    }
+
+    /// Combine matcher with other matcher which matches when exactly one of
+    /// them matches
+    fn xor<M>(self, other: M) -> Xor<Self, M>
+    where
+        Self: Sized,
+        M: KeyValueMatcher,
+    {
+        Xor::new(self, other)
+    }
+
+    /// Negate matcher
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not::new(self)
+    }
+
+    /// Attach label to matcher which is used for diagnostics
+    fn with_label<T>(self, label: T) -> Labeled<Self>
+    where
+        Self: Sized,
+        T: Into<String>,
+    {
+        Labeled::new(label, self)
+    }
+}
+
+impl<M> KeyValueMatcherExt for M where M: KeyValueMatcher + ?Sized {}
+
 /// Any matcher which always returns true and matches any host
 #[derive(Clone)]
 pub struct Any;
@@ -39,12 +258,13 @@ impl Matcher for Any {
 }
 
 impl KeyValueMatcher for Any {
-    fn matches_key_value(&self, _values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, _values: &Parameters<'_>) -> bool {
         true
     }
 }
 
 /// And matcher which matches only when both left and right matches
+#[derive(Clone)]
 pub struct And<L, R> {
     left: L,
     right: R,
@@ -65,6 +285,24 @@ where
     fn matches_value(&self, value: &str) -> bool {
         self.left.matches_value(value) && self.right.matches_value(value)
     }
+
+    fn decide(&self, value: &str) -> Decision {
+        let left = self.left.decide(value);
+        if !left.allowed {
+            return left;
+        }
+        let right = self.right.decide(value);
+        if !right.allowed || left.rule.is_none() {
+            right
+        } else {
+            left
+        }
+    }
+
+    fn check_config(&self) {
+        self.left.check_config();
+        self.right.check_config();
+    }
 }
 
 impl<L, R> KeyValueMatcher for And<L, R>
@@ -72,12 +310,13 @@ where
     L: KeyValueMatcher,
     R: KeyValueMatcher,
 {
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
         self.left.matches_key_value(values) && self.right.matches_key_value(values)
     }
 }
 
 /// Or matcher which matches when either left and right matches
+#[derive(Clone)]
 pub struct Or<L, R> {
     left: L,
     right: R,
@@ -95,7 +334,7 @@ where
     L: KeyValueMatcher,
     R: KeyValueMatcher,
 {
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
         self.left.matches_key_value(values) || self.right.matches_key_value(values)
     }
 }
@@ -108,6 +347,386 @@ where
     fn matches_value(&self, value: &str) -> bool {
         self.left.matches_value(value) || self.right.matches_value(value)
     }
+
+    fn decide(&self, value: &str) -> Decision {
+        let left = self.left.decide(value);
+        if left.allowed {
+            return left;
+        }
+        let right = self.right.decide(value);
+        if right.allowed || left.rule.is_none() {
+            right
+        } else {
+            left
+        }
+    }
+
+    fn check_config(&self) {
+        self.left.check_config();
+        self.right.check_config();
+    }
+}
+
+/// Xor matcher which matches when exactly one of left and right matches
+#[derive(Clone)]
+pub struct Xor<L, R> {
+    left: L,
+    right: R,
+}
+
+impl<L, R> Xor<L, R> {
+    /// Create new xor matcher
+    pub fn new(left: L, right: R) -> Self {
+        Self { left, right }
+    }
+}
+
+impl<L, R> Matcher for Xor<L, R>
+where
+    L: Matcher,
+    R: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.left.matches_value(value) != self.right.matches_value(value)
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        let left = self.left.decide(value);
+        let right = self.right.decide(value);
+        match (left.allowed, right.allowed) {
+            (true, false) => left,
+            // value matched by both isn't decided by single rule
+            (true, true) => Decision::new(false),
+            (false, false) if left.rule.is_some() => left,
+            (false, _) => right,
+        }
+    }
+
+    fn check_config(&self) {
+        self.left.check_config();
+        self.right.check_config();
+    }
+}
+
+impl<L, R> KeyValueMatcher for Xor<L, R>
+where
+    L: KeyValueMatcher,
+    R: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.left.matches_key_value(values) != self.right.matches_key_value(values)
+    }
+}
+
+/// Not matcher which matches when inner matcher doesn't match
+#[derive(Clone)]
+pub struct Not<M> {
+    matcher: M,
+}
+
+impl<M> Not<M> {
+    /// Create new not matcher
+    pub fn new(matcher: M) -> Self {
+        Self { matcher }
+    }
+}
+
+impl<M> Matcher for Not<M>
+where
+    M: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        !self.matcher.matches_value(value)
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        let decision = self.matcher.decide(value);
+        Decision {
+            allowed: !decision.allowed,
+            rule: decision.rule,
+        }
+    }
+
+    fn check_config(&self) {
+        self.matcher.check_config();
+    }
+}
+
+impl<M> KeyValueMatcher for Not<M>
+where
+    M: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        !self.matcher.matches_key_value(values)
+    }
+}
+
+/// Labeled matcher which attaches label to inner matcher
+///
+/// Label doesn't change result of inner matcher. Label is reported as rule of
+/// [`Decision`] only when inner matcher matches and doesn't report its own
+/// rule. With `tracing` feature result of inner matcher is logged along with
+/// label.
+#[derive(Clone)]
+pub struct Labeled<M> {
+    label: Arc<str>,
+    matcher: M,
+}
+
+impl<M> Labeled<M> {
+    /// Create new labeled matcher
+    pub fn new<T>(label: T, matcher: M) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            label: label.into().into(),
+            matcher,
+        }
+    }
+
+    /// Return label of matcher
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl<M> Matcher for Labeled<M>
+where
+    M: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        let matched = self.matcher.matches_value(value);
+        #[cfg(feature = "tracing")]
+        tracing::trace!("matcher {} matched {}: {}", self.label, value, matched);
+        matched
+    }
+
+    fn check_config(&self) {
+        self.matcher.check_config();
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        let decision = self.matcher.decide(value);
+        // plain non match isn't decided by labeled matcher so it is passed
+        // through without rule
+        if decision.rule.is_some() || !decision.allowed {
+            return decision;
+        }
+        decision.with_rule(Arc::clone(&self.label))
+    }
+}
+
+impl<M> KeyValueMatcher for Labeled<M>
+where
+    M: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        let matched = self.matcher.matches_key_value(values);
+        #[cfg(feature = "tracing")]
+        tracing::trace!("key value matcher {} matched: {}", self.label, matched);
+        matched
+    }
+}
+
+/// Any of matcher which matches when any matcher of collection matches
+///
+/// Collection can be any type whose reference can be iterated such as `Vec`,
+/// array or `HashSet`. Empty collection never matches.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{AnyOf, Matcher as _};
+///
+/// let matcher = AnyOf::new(vec!["a.com", "b.com"]);
+/// assert!(matcher.matches_value("b.com"));
+/// assert!(!matcher.matches_value("c.com"));
+/// ```
+#[derive(Clone)]
+pub struct AnyOf<C> {
+    matchers: C,
+}
+
+impl<C> AnyOf<C> {
+    /// Create new any of matcher
+    pub fn new(matchers: C) -> Self {
+        Self { matchers }
+    }
+}
+
+impl<C> Matcher for AnyOf<C>
+where
+    for<'a> &'a C: IntoIterator,
+    for<'a> <&'a C as IntoIterator>::Item: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.matchers
+            .into_iter()
+            .any(|matcher| matcher.matches_value(value))
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        decide_any(&self.matchers, value)
+    }
+
+    fn check_config(&self) {
+        for matcher in &self.matchers {
+            matcher.check_config();
+        }
+    }
+}
+
+impl<C> KeyValueMatcher for AnyOf<C>
+where
+    for<'a> &'a C: IntoIterator,
+    for<'a> <&'a C as IntoIterator>::Item: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.matchers
+            .into_iter()
+            .any(|matcher| matcher.matches_key_value(values))
+    }
+}
+
+/// Decide using first matcher which allows value. When no matcher allows
+/// value, decision of first matcher which reports rule is used
+fn decide_any<I>(matchers: I, value: &str) -> Decision
+where
+    I: IntoIterator,
+    I::Item: Matcher,
+{
+    let mut denied = Decision::new(false);
+    for matcher in matchers {
+        let decision = matcher.decide(value);
+        if decision.allowed {
+            return decision;
+        }
+        if denied.rule.is_none() {
+            denied = decision;
+        }
+    }
+    denied
+}
+
+/// All of matcher which matches when all matcher of collection matches
+///
+/// Collection can be any type whose reference can be iterated such as `Vec`,
+/// array or `HashSet`. Empty collection always matches.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{AllOf, HostGlob, Matcher as _};
+///
+/// let matcher = AllOf::new(vec![
+///     HostGlob::new("*.example.com").unwrap(),
+///     HostGlob::new("api.*.*").unwrap(),
+/// ]);
+/// assert!(matcher.matches_value("api.example.com"));
+/// assert!(!matcher.matches_value("www.example.com"));
+/// ```
+#[derive(Clone)]
+pub struct AllOf<C> {
+    matchers: C,
+}
+
+impl<C> AllOf<C> {
+    /// Create new all of matcher
+    pub fn new(matchers: C) -> Self {
+        Self { matchers }
+    }
+}
+
+impl<C> Matcher for AllOf<C>
+where
+    for<'a> &'a C: IntoIterator,
+    for<'a> <&'a C as IntoIterator>::Item: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.matchers
+            .into_iter()
+            .all(|matcher| matcher.matches_value(value))
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        let mut allowed = Decision::new(true);
+        for matcher in &self.matchers {
+            let decision = matcher.decide(value);
+            if !decision.allowed {
+                return decision;
+            }
+            if allowed.rule.is_none() {
+                allowed = decision;
+            }
+        }
+        allowed
+    }
+
+    fn check_config(&self) {
+        for matcher in &self.matchers {
+            matcher.check_config();
+        }
+    }
+}
+
+impl<C> KeyValueMatcher for AllOf<C>
+where
+    for<'a> &'a C: IntoIterator,
+    for<'a> <&'a C as IntoIterator>::Item: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.matchers
+            .into_iter()
+            .all(|matcher| matcher.matches_key_value(values))
+    }
+}
+
+/// Matcher created from closure
+///
+/// Implements [`Matcher`] for closure of `Fn(&str) -> bool` and
+/// [`KeyValueMatcher`] for closure of `Fn(&Parameters<'_>) -> bool`.
+/// Created using [`from_fn`].
+#[derive(Clone)]
+pub struct FromFn<F> {
+    f: F,
+}
+
+/// Create matcher from closure
+///
+/// Matcher is `Clone` when closure is `Clone` so it can be used with
+/// `AllowedHostLayer`.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::forwarded::Parameters;
+/// use tower_allowed_hosts::matcher::from_fn;
+///
+/// let layer = tower_allowed_hosts::AllowedHostLayer::new(from_fn(|host: &str| {
+///     host.ends_with(".example.com")
+/// }))
+/// .with_forwarded_matcher(from_fn(|values: &Parameters<'_>| {
+///     values.contains_key("signature")
+/// }));
+/// ```
+pub fn from_fn<F>(f: F) -> FromFn<F> {
+    FromFn { f }
+}
+
+impl<F> Matcher for FromFn<F>
+where
+    F: Fn(&str) -> bool,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        (self.f)(value)
+    }
+}
+
+impl<F> KeyValueMatcher for FromFn<F>
+where
+    F: Fn(&Parameters<'_>) -> bool,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        (self.f)(values)
+    }
 }
 
 impl Matcher for String {
@@ -129,7 +748,7 @@ impl Matcher for () {
 }
 
 impl KeyValueMatcher for () {
-    fn matches_key_value(&self, _values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, _values: &Parameters<'_>) -> bool {
         false
     }
 }
@@ -143,13 +762,6 @@ impl<const MULTI_WILDCARD: char, const SINGLE_WILDCARD: char> Matcher
     }
 }
 
-#[cfg(feature = "regex")]
-impl Matcher for Regex {
-    fn matches_value(&self, value: &str) -> bool {
-        self.is_match(value)
-    }
-}
-
 impl<M> Matcher for Option<M>
 where
     M: Matcher,
@@ -161,13 +773,27 @@ where
             false
         }
     }
+
+    fn decide(&self, value: &str) -> Decision {
+        if let Some(matcher) = self {
+            matcher.decide(value)
+        } else {
+            Decision::new(false)
+        }
+    }
+
+    fn check_config(&self) {
+        if let Some(matcher) = self {
+            matcher.check_config();
+        }
+    }
 }
 
 impl<M> KeyValueMatcher for Option<M>
 where
     M: KeyValueMatcher,
 {
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
         if let Some(matcher) = self {
             matcher.matches_key_value(values)
         } else {
@@ -178,46 +804,203 @@ where
 
 impl<M> Matcher for Box<M>
 where
-    M: Matcher,
+    M: Matcher + ?Sized,
 {
     fn matches_value(&self, value: &str) -> bool {
         (**self).matches_value(value)
     }
+
+    fn decide(&self, value: &str) -> Decision {
+        (**self).decide(value)
+    }
+
+    fn check_config(&self) {
+        (**self).check_config();
+    }
 }
 
 impl<M> KeyValueMatcher for Box<M>
 where
-    M: KeyValueMatcher,
+    M: KeyValueMatcher + ?Sized,
 {
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        (**self).matches_key_value(values)
+    }
+}
+
+impl<M> Matcher for Arc<M>
+where
+    M: Matcher + ?Sized,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        (**self).matches_value(value)
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        (**self).decide(value)
+    }
+
+    fn check_config(&self) {
+        (**self).check_config();
+    }
+}
+
+impl<M> KeyValueMatcher for Arc<M>
+where
+    M: KeyValueMatcher + ?Sized,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
         (**self).matches_key_value(values)
     }
 }
 
 impl<M> Matcher for &M
 where
-    M: Matcher,
+    M: Matcher + ?Sized,
 {
     fn matches_value(&self, value: &str) -> bool {
         (**self).matches_value(value)
     }
+
+    fn decide(&self, value: &str) -> Decision {
+        (**self).decide(value)
+    }
+
+    fn check_config(&self) {
+        (**self).check_config();
+    }
 }
 
 impl<M> KeyValueMatcher for &M
 where
-    M: KeyValueMatcher,
+    M: KeyValueMatcher + ?Sized,
 {
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
         (**self).matches_key_value(values)
     }
 }
 
+/// Slice of matcher matches when any of matcher matches
+impl<M> Matcher for [M]
+where
+    M: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.iter().any(|matcher| matcher.matches_value(value))
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        decide_any(self, value)
+    }
+
+    fn check_config(&self) {
+        self.iter().for_each(Matcher::check_config);
+    }
+}
+
+/// Slice of matcher matches when any of matcher matches
+impl<M> KeyValueMatcher for [M]
+where
+    M: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.iter().any(|matcher| matcher.matches_key_value(values))
+    }
+}
+
+/// Array of matcher matches when any of matcher matches
+impl<M, const N: usize> Matcher for [M; N]
+where
+    M: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.as_slice().matches_value(value)
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        self.as_slice().decide(value)
+    }
+
+    fn check_config(&self) {
+        self.as_slice().check_config();
+    }
+}
+
+/// Array of matcher matches when any of matcher matches
+impl<M, const N: usize> KeyValueMatcher for [M; N]
+where
+    M: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.as_slice().matches_key_value(values)
+    }
+}
+
+/// Vec of matcher matches when any of matcher matches
+impl<M> Matcher for Vec<M>
+where
+    M: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.as_slice().matches_value(value)
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        self.as_slice().decide(value)
+    }
+
+    fn check_config(&self) {
+        self.as_slice().check_config();
+    }
+}
+
+/// Vec of matcher matches when any of matcher matches
+impl<M> KeyValueMatcher for Vec<M>
+where
+    M: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.as_slice().matches_key_value(values)
+    }
+}
+
+/// Hash set of matcher matches when any of matcher matches
+///
+/// Each matcher is checked in turn, use [`HostSet`] for constant time lookup
+/// of exact hosts
+impl<M, S> Matcher for HashSet<M, S>
+where
+    M: Matcher,
+{
+    fn matches_value(&self, value: &str) -> bool {
+        self.iter().any(|matcher| matcher.matches_value(value))
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        decide_any(self, value)
+    }
+
+    fn check_config(&self) {
+        self.iter().for_each(Matcher::check_config);
+    }
+}
+
+/// Hash set of matcher matches when any of matcher matches
+impl<M, S> KeyValueMatcher for HashSet<M, S>
+where
+    M: KeyValueMatcher,
+{
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        self.iter().any(|matcher| matcher.matches_key_value(values))
+    }
+}
+
 impl<S, M> KeyValueMatcher for (S, M)
 where
     S: Matcher,
     M: Matcher,
 {
-    fn matches_key_value(&self, values: &HashMap<String, String>) -> bool {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
         let (key_matcher, value_matcher) = self;
         values
             .iter()
@@ -227,11 +1010,17 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
-    use crate::matcher::{And, Any, KeyValueMatcher as _, Matcher as _, Or};
+    use crate::forwarded::Parameters;
+    use crate::matcher::{
+        AllOf, And, Any, AnyOf, Decision, KeyValueMatcher, KeyValueMatcherExt, Labeled, Matcher,
+        MatcherExt, Not, Or, RuleSet, Xor, from_fn,
+    };
 
-    fn forwarded_map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    fn forwarded_map(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
         pairs
             .iter()
             .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
@@ -249,7 +1038,7 @@ mod tests {
     fn any_forwarded_always_matches() {
         let m = Any;
         let data = forwarded_map(&[("by", "server1")]);
-        assert!(m.matches_key_value(&data));
+        assert!(m.matches_key_value(&Parameters::new(&data)));
     }
 
     #[test]
@@ -268,7 +1057,7 @@ mod tests {
         let fwd_m: () = ();
         assert!(!host_m.matches_value("anything"));
         let data = forwarded_map(&[("foo", "bar")]);
-        assert!(!fwd_m.matches_key_value(&data));
+        assert!(!fwd_m.matches_key_value(&Parameters::new(&data)));
     }
 
     #[test]
@@ -303,14 +1092,40 @@ mod tests {
         assert!(boxed.matches_value("host.com"));
     }
 
+    #[test]
+    fn closure_matchers() {
+        let host = from_fn(|value: &str| value.ends_with(".example.com"));
+        assert!(host.matches_value("api.example.com"));
+        assert!(!host.matches_value("example.org"));
+
+        let forwarded = from_fn(|values: &Parameters<'_>| values.contains_key("sig"));
+        assert!(forwarded.matches_key_value(&Parameters::new(&forwarded_map(&[("sig", "123")]))));
+        assert!(
+            !forwarded.matches_key_value(&Parameters::new(&forwarded_map(&[("by", "proxy1")])))
+        );
+    }
+
+    #[test]
+    fn arc_and_dyn_matchers() {
+        let arc: Arc<dyn Matcher + Send + Sync> = Arc::new("host.com");
+        assert!(arc.clone().matches_value("host.com"));
+        assert!(!arc.matches_value("other.com"));
+
+        let boxed: Box<dyn Matcher> = Box::new(from_fn(|value: &str| value == "host.com"));
+        assert!(boxed.matches_value("host.com"));
+
+        let forwarded: Arc<dyn KeyValueMatcher> = Arc::new(("by", "proxy1"));
+        assert!(forwarded.matches_key_value(&Parameters::new(&forwarded_map(&[("by", "proxy1")]))));
+    }
+
     #[test]
     fn forwarded_tuple_matcher() {
         let fwd = ("by", "proxy1");
         let data = forwarded_map(&[("by", "proxy1"), ("host", "example.com")]);
-        assert!(fwd.matches_key_value(&data));
+        assert!(fwd.matches_key_value(&Parameters::new(&data)));
 
         let data2 = forwarded_map(&[("by", "proxy2")]);
-        assert!(!fwd.matches_key_value(&data2));
+        assert!(!fwd.matches_key_value(&Parameters::new(&data2)));
     }
 
     #[test]
@@ -321,12 +1136,183 @@ mod tests {
         let or = Or::new(m1, m2);
 
         let data = forwarded_map(&[("by", "proxy1"), ("sig", "123")]);
-        assert!(and.matches_key_value(&data));
-        assert!(or.matches_key_value(&data));
+        assert!(and.matches_key_value(&Parameters::new(&data)));
+        assert!(or.matches_key_value(&Parameters::new(&data)));
 
         let data2 = forwarded_map(&[("by", "proxy2"), ("sig", "123")]);
-        assert!(!and.matches_key_value(&data2));
-        assert!(or.matches_key_value(&data2));
+        assert!(!and.matches_key_value(&Parameters::new(&data2)));
+        assert!(or.matches_key_value(&Parameters::new(&data2)));
+    }
+
+    #[test]
+    fn xor_and_not_matcher() {
+        let xor = Xor::new("foo.com", "foo.com".to_string());
+        assert!(!xor.matches_value("foo.com"));
+        let xor = Xor::new("foo.com", "bar.com");
+        assert!(xor.matches_value("foo.com"));
+        assert!(!xor.matches_value("baz.com"));
+
+        let not = Not::new("foo.com");
+        assert!(!not.matches_value("foo.com"));
+        assert!(not.matches_value("bar.com"));
+    }
+
+    #[test]
+    fn matcher_ext_combinators() {
+        let matcher = MatcherExt::or("foo.com", "bar.com").and(MatcherExt::not("bar.com"));
+        assert!(matcher.matches_value("foo.com"));
+        assert!(!matcher.matches_value("bar.com"));
+
+        let labeled: Labeled<_> = MatcherExt::with_label("foo.com", "foo");
+        assert_eq!(labeled.label(), "foo");
+        assert!(labeled.matches_value("foo.com"));
+        assert!(MatcherExt::xor("foo.com", "bar.com").matches_value("bar.com"));
+    }
+
+    struct ConfigCounter<'a>(&'a AtomicUsize);
+
+    impl Matcher for ConfigCounter<'_> {
+        fn matches_value(&self, _value: &str) -> bool {
+            false
+        }
+
+        fn check_config(&self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn check_config_forwarded() {
+        let checked = AtomicUsize::new(0);
+        let counter = || ConfigCounter(&checked);
+        let matcher = AnyOf::new(vec![
+            MatcherExt::or(counter(), counter().not()),
+            MatcherExt::or(counter(), counter().not()),
+        ]);
+        Some(Box::new(matcher)).check_config();
+        assert_eq!(checked.load(Ordering::Relaxed), 4);
+
+        [counter(), counter()].check_config();
+        AllOf::new([counter()]).check_config();
+        MatcherExt::xor(counter(), counter()).check_config();
+        Arc::new(MatcherExt::with_label(counter(), "label").and(counter())).check_config();
+        assert_eq!(checked.load(Ordering::Relaxed), 11);
+    }
+
+    #[test]
+    fn combinator_decision() {
+        let rule_set = || RuleSet::new().deny("deny.com").allow("allow.com");
+        let denied = |decision: Decision| (decision.allowed, decision.rule);
+        let deny_rule = Some(Arc::from("deny rule #0"));
+        let allow_rule = Some(Arc::from("allow rule #1"));
+
+        let any_of = AnyOf::new([rule_set()]);
+        assert_eq!(
+            denied(any_of.decide("deny.com")),
+            (false, deny_rule.clone())
+        );
+        assert_eq!(
+            denied(any_of.decide("allow.com")),
+            (true, allow_rule.clone())
+        );
+        assert_eq!(denied(any_of.decide("other.com")), (false, None));
+        assert_eq!(
+            denied(vec![rule_set()].decide("deny.com")),
+            (false, deny_rule.clone())
+        );
+        assert_eq!(
+            denied(AllOf::new([rule_set()]).decide("deny.com")),
+            (false, deny_rule.clone())
+        );
+        assert_eq!(
+            denied(MatcherExt::or("other.com", rule_set()).decide("deny.com")),
+            (false, deny_rule.clone())
+        );
+        assert_eq!(
+            denied(MatcherExt::and(rule_set(), "allow.com").decide("allow.com")),
+            (true, allow_rule.clone())
+        );
+        assert_eq!(
+            denied(MatcherExt::xor("other.com", rule_set()).decide("allow.com")),
+            (true, allow_rule)
+        );
+        assert_eq!(
+            denied(MatcherExt::not(rule_set()).decide("deny.com")),
+            (true, deny_rule)
+        );
+    }
+
+    #[test]
+    fn labeled_decision() {
+        let labeled = MatcherExt::with_label("a.com", "main");
+        let matched = labeled.decide("a.com");
+        assert!(matched.allowed);
+        assert_eq!(matched.rule.as_deref(), Some("main"));
+
+        let not_matched = labeled.decide("b.com");
+        assert!(!not_matched.allowed);
+        assert_eq!(not_matched.rule, None);
+    }
+
+    #[test]
+    fn key_value_matcher_ext_combinators() {
+        let matcher = KeyValueMatcherExt::or(("by", "proxy1"), ("by", "proxy2"))
+            .and(KeyValueMatcherExt::not(("sig", "bad")));
+        assert!(matcher.matches_key_value(&Parameters::new(&forwarded_map(&[("by", "proxy2")]))));
+        assert!(!matcher.matches_key_value(&Parameters::new(&forwarded_map(&[("by", "proxy3")]))));
+        let data = forwarded_map(&[("by", "proxy1"), ("sig", "bad")]);
+        assert!(!matcher.matches_key_value(&Parameters::new(&data)));
+        assert!(
+            KeyValueMatcherExt::with_label(("by", "proxy1"), "proxy")
+                .matches_key_value(&Parameters::new(&data))
+        );
+    }
+
+    #[test]
+    fn collection_matchers() {
+        let array = ["foo.com", "bar.com"];
+        assert!(array.matches_value("bar.com"));
+        assert!(!array.matches_value("baz.com"));
+        assert!(array.as_slice().matches_value("foo.com"));
+
+        let vec = vec!["foo.com".to_string()];
+        assert!(vec.matches_value("foo.com"));
+        assert!(!Vec::<String>::new().matches_value("foo.com"));
+
+        let set = HashSet::from(["foo.com", "bar.com"]);
+        assert!(set.matches_value("foo.com"));
+        assert!(!set.matches_value("baz.com"));
+
+        let forwarded = vec![("by", "proxy1"), ("by", "proxy2")];
+        assert!(forwarded.matches_key_value(&Parameters::new(&forwarded_map(&[("by", "proxy2")]))));
+    }
+
+    #[test]
+    fn any_of_and_all_of_matchers() {
+        let any_of = AnyOf::new(vec!["foo.com", "bar.com"]);
+        assert!(any_of.matches_value("foo.com"));
+        assert!(!any_of.matches_value("baz.com"));
+        assert!(!AnyOf::new(Vec::<String>::new()).matches_value("foo.com"));
+
+        let all_of = AllOf::new(HashSet::from([
+            "foo.com".to_string(),
+            "foo.com".to_string(),
+        ]));
+        assert!(all_of.matches_value("foo.com"));
+        let all_of = AllOf::new(["foo.com", "bar.com"]);
+        assert!(!all_of.matches_value("foo.com"));
+        assert!(AllOf::new(Vec::<String>::new()).matches_value("foo.com"));
+
+        let forwarded = AllOf::new([("by", "proxy1"), ("sig", "123")]);
+        assert!(
+            forwarded.matches_key_value(&Parameters::new(&forwarded_map(&[
+                ("by", "proxy1"),
+                ("sig", "123")
+            ])))
+        );
+        assert!(
+            !forwarded.matches_key_value(&Parameters::new(&forwarded_map(&[("by", "proxy1")])))
+        );
     }
 
     #[cfg(feature = "regex")]
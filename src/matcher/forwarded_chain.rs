@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use crate::forwarded::{ForwardedChain, Node, NodeName, Parameters};
+use crate::matcher::KeyValueMatcher;
+use crate::proxy::HopSelection;
+
+/// Trait for selecting trusted element from whole `Forwarded` chain
+///
+/// Unlike [`KeyValueMatcher`] which judges one element at a time, chain
+/// matcher receives every element of all `Forwarded` headers in order so rules
+/// spanning multiple hops can be written. Host of returned element is used as
+/// host of request.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::forwarded::{ForwardedChain, Parameters};
+/// use tower_allowed_hosts::matcher::ForwardedChainMatcher;
+///
+/// /// Last two hops must be added by our two load balancers
+/// struct LoadBalancers;
+///
+/// impl ForwardedChainMatcher for LoadBalancers {
+///     fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>> {
+///         match chain.elements() {
+///             [.., outer, inner]
+///                 if outer.get("by") == Some("_lb1") && inner.get("by") == Some("_lb2") =>
+///             {
+///                 Some(*outer)
+///             }
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+///     .with_forwarded_chain_matcher(LoadBalancers);
+/// ```
+pub trait ForwardedChainMatcher {
+    /// Select trusted element of chain whose host is used, returns `None` when
+    /// no element can be trusted
+    fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>>;
+}
+
+/// Adapter which creates [`ForwardedChainMatcher`] from per element
+/// [`KeyValueMatcher`]
+///
+/// Element is selected according to [`HopSelection`] and it is only used when
+/// it contains host and is accepted by key value matcher.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::EachElement;
+/// use tower_allowed_hosts::proxy::HopSelection;
+///
+/// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+///     .with_forwarded_chain_matcher(
+///         EachElement::new(("by", "_proxy")).with_hop_selection(HopSelection::Rightmost),
+///     );
+/// ```
+#[derive(Debug, Clone)]
+pub struct EachElement<M> {
+    matcher: M,
+    hop_selection: HopSelection,
+}
+
+impl<M> EachElement<M> {
+    /// Create new adapter using [`HopSelection::FirstMatch`]
+    pub fn new(matcher: M) -> Self {
+        Self {
+            matcher,
+            hop_selection: HopSelection::default(),
+        }
+    }
+
+    /// Set strategy used to select element
+    #[must_use]
+    pub fn with_hop_selection(mut self, hop_selection: HopSelection) -> Self {
+        self.hop_selection = hop_selection;
+        self
+    }
+}
+
+impl<M> ForwardedChainMatcher for EachElement<M>
+where
+    M: KeyValueMatcher,
+{
+    fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>> {
+        let elements = chain.elements();
+        let selected = match self.hop_selection {
+            HopSelection::FirstMatch => {
+                return elements
+                    .iter()
+                    .find(|parameters| {
+                        parameters.contains_key("host")
+                            && self.matcher.matches_key_value(parameters)
+                    })
+                    .copied();
+            }
+            HopSelection::Rightmost => elements.last(),
+            HopSelection::TrustedHops(hops) => {
+                hops.checked_sub(1)
+                    .and_then(|index| elements.iter().rev().nth(index))
+            }
+            HopSelection::TrustedChain => select_trusted_chain(chain),
+        };
+        selected
+            .filter(|parameters| {
+                parameters.contains_key("host") && self.matcher.matches_key_value(parameters)
+            })
+            .copied()
+    }
+}
+
+/// Select outermost element of contiguous chain of trusted proxies by walking
+/// elements from right to left starting from peer address
+fn select_trusted_chain<'c, 'a>(chain: &'c ForwardedChain<'a>) -> Option<&'c Parameters<'a>> {
+    let trusted_proxies = chain.trusted_proxies()?;
+    if !chain
+        .peer_addr()
+        .is_some_and(|peer| trusted_proxies.contains(peer))
+    {
+        return None;
+    }
+    let mut selected = None;
+    for parameters in chain.elements().iter().rev() {
+        selected = Some(parameters);
+        let for_trusted = parameters
+            .get("for")
+            .and_then(|node| node.parse::<Node>().ok())
+            .is_some_and(
+                |node| matches!(node.name, NodeName::Ip(ip) if trusted_proxies.contains(ip)),
+            );
+        if !for_trusted {
+            break;
+        }
+    }
+    selected
+}
+
+impl<M> ForwardedChainMatcher for &M
+where
+    M: ForwardedChainMatcher + ?Sized,
+{
+    fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>> {
+        (**self).select(chain)
+    }
+}
+
+impl<M> ForwardedChainMatcher for Box<M>
+where
+    M: ForwardedChainMatcher + ?Sized,
+{
+    fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>> {
+        (**self).select(chain)
+    }
+}
+
+impl<M> ForwardedChainMatcher for Arc<M>
+where
+    M: ForwardedChainMatcher + ?Sized,
+{
+    fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>> {
+        (**self).select(chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::forwarded::{ForwardedChain, Parameters};
+    use crate::matcher::{Any, EachElement, ForwardedChainMatcher};
+    use crate::proxy::{HopSelection, TrustedProxies};
+
+    fn pairs(values: &[&[(&str, &str)]]) -> Vec<Vec<(String, String)>> {
+        values
+            .iter()
+            .map(|element| {
+                element
+                    .iter()
+                    .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn selected_host<M>(matcher: &M, chain: &ForwardedChain<'_>) -> Option<String>
+    where
+        M: ForwardedChainMatcher,
+    {
+        matcher
+            .select(chain)
+            .and_then(|parameters| parameters.get("host").map(ToString::to_string))
+    }
+
+    #[test]
+    fn each_element_hop_selection() {
+        let elements = pairs(&[
+            &[("for", "1.2.3.4"), ("host", "evil.com"), ("sig", "1")],
+            &[("for", "203.0.113.9"), ("host", "example.com")],
+            &[("for", "10.0.0.1")],
+        ]);
+        let trusted_proxies = TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]);
+        let chain = ForwardedChain::new(elements.iter().map(|pairs| Parameters::new(pairs)))
+            .with_peer_addr("10.0.0.2".parse().unwrap())
+            .with_trusted_proxies(&trusted_proxies);
+
+        let first_match = EachElement::new(Any);
+        assert_eq!(
+            selected_host(&first_match, &chain).as_deref(),
+            Some("evil.com")
+        );
+        let first_match = EachElement::new(("sig", "2"));
+        assert_eq!(selected_host(&first_match, &chain), None);
+
+        let rightmost = EachElement::new(Any).with_hop_selection(HopSelection::Rightmost);
+        assert_eq!(selected_host(&rightmost, &chain), None);
+
+        let trusted_hops = EachElement::new(Any).with_hop_selection(HopSelection::TrustedHops(2));
+        assert_eq!(
+            selected_host(&trusted_hops, &chain).as_deref(),
+            Some("example.com")
+        );
+
+        let trusted_chain = EachElement::new(Any).with_hop_selection(HopSelection::TrustedChain);
+        assert_eq!(
+            selected_host(&trusted_chain, &chain).as_deref(),
+            Some("example.com")
+        );
+        let untrusted_peer =
+            ForwardedChain::new(elements.iter().map(|pairs| Parameters::new(pairs)))
+                .with_peer_addr("192.0.2.1".parse().unwrap())
+                .with_trusted_proxies(&trusted_proxies);
+        assert_eq!(selected_host(&trusted_chain, &untrusted_peer), None);
+    }
+}
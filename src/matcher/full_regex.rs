@@ -0,0 +1,131 @@
+use regex::Regex;
+
+use crate::matcher::Matcher;
+
+/// Matcher which requires regex pattern to match whole host
+///
+/// Pattern is anchored automatically so `FullRegex::new("example.com")` doesn't
+/// match `example.com.evil.net` unlike [`Regex`] which uses unanchored
+/// [`Regex::is_match`].
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{FullRegex, Matcher as _};
+///
+/// let matcher = FullRegex::new(r"[a-z]+\.example\.com").unwrap();
+/// assert!(matcher.matches_value("api.example.com"));
+/// assert!(!matcher.matches_value("api.example.com.evil.net"));
+/// ```
+#[derive(Clone)]
+pub struct FullRegex {
+    regex: Regex,
+}
+
+impl FullRegex {
+    /// Create new full regex matcher from provided pattern
+    ///
+    /// # Errors
+    /// Returns error if pattern is invalid regex
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        let regex = Regex::new(&format!("^(?:{pattern})$"))?;
+        Ok(Self { regex })
+    }
+
+    /// Return anchored regex used by matcher
+    #[must_use]
+    pub fn as_regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+impl Matcher for FullRegex {
+    fn matches_value(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+}
+
+/// Check if every match of regex is anchored at start and end of haystack
+///
+/// Pattern is parsed so alternation such as `^a.com|b.com$` is unanchored
+/// while flags or groups such as `(?i)^x$` doesn't affect result. Multi line
+/// anchor such as `(?m)^` is considered unanchored.
+///
+/// # Example
+/// ```
+/// use regex::Regex;
+/// use tower_allowed_hosts::matcher::is_anchored;
+///
+/// assert!(is_anchored(&Regex::new(r"(?i)^example\.com$").unwrap()));
+/// assert!(!is_anchored(&Regex::new(r"^a\.com|b\.com$").unwrap()));
+/// ```
+#[must_use]
+pub fn is_anchored(regex: &Regex) -> bool {
+    use regex_syntax::hir::Look;
+
+    regex_syntax::parse(regex.as_str()).is_ok_and(|hir| {
+        let properties = hir.properties();
+        properties.look_set_prefix().contains(Look::Start)
+            && properties.look_set_suffix().contains(Look::End)
+    })
+}
+
+/// Regex is matched using unanchored [`Regex::is_match`] so pattern such as
+/// `example.com` also matches `example.com.evil.net`. With `tracing` feature a
+/// warning is logged when layer is created with unanchored pattern. Prefer
+/// [`FullRegex`] which anchors pattern automatically.
+impl Matcher for Regex {
+    fn matches_value(&self, value: &str) -> bool {
+        self.is_match(value)
+    }
+
+    fn check_config(&self) {
+        #[cfg(feature = "tracing")]
+        if !is_anchored(self) {
+            tracing::warn!(
+                "regex pattern {} is not anchored and matches partial host, use FullRegex instead",
+                self.as_str()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use crate::matcher::{FullRegex, Matcher as _, is_anchored};
+
+    #[test]
+    fn full_regex_matches_whole_host() {
+        let matcher = FullRegex::new("example.com").unwrap();
+        assert!(matcher.matches_value("example.com"));
+        assert!(matcher.matches_value("exampleXcom"));
+        assert!(!matcher.matches_value("example.com.evil.net"));
+        assert!(!matcher.matches_value("evil.example.com"));
+
+        let alternation = FullRegex::new("a.com|b.com").unwrap();
+        assert!(alternation.matches_value("b.com"));
+        assert!(!alternation.matches_value("a.com.evil.net"));
+        assert!(FullRegex::new("(").is_err());
+    }
+
+    #[test]
+    fn unanchored_regex() {
+        let anchored = |pattern: &str| is_anchored(&Regex::new(pattern).unwrap());
+        assert!(anchored("^example.com$"));
+        assert!(anchored(r"\Aexample.com\z"));
+        assert!(!anchored("example.com"));
+        assert!(!anchored(r"^example.com\$"));
+        assert!(anchored(r"(?i)^example\.com$"));
+        assert!(anchored(r"^(?:a\.com|b\.com)$"));
+        assert!(anchored(r"^a\.com$|^b\.com$"));
+        assert!(!anchored(r"^a.com|b.com$"));
+        assert!(!anchored(r"(?m)^example\.com$"));
+        assert!(is_anchored(FullRegex::new("a|b").unwrap().as_regex()));
+        assert!(
+            Regex::new("example.com")
+                .unwrap()
+                .matches_value("example.com.evil.net")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use crate::error::PatternError;
+use crate::host::{split_port, validate_port};
+use crate::matcher::Matcher;
+
+/// Pattern of single label of glob
+#[derive(Clone)]
+enum Label {
+    /// `**` which matches one or more labels
+    Many,
+    /// label which may contain `*` and `?`
+    Glob(Vec<char>),
+}
+
+/// Matcher which matches host using DNS label aware glob pattern
+///
+/// Unlike wildcard matching of flat string, wildcard never crosses `.`
+/// - `*` as whole label matches exactly one label
+/// - `**` as whole label matches one or more labels
+/// - `*` inside a label matches any characters within that label
+/// - `?` matches exactly one unicode character within a label
+///
+/// Host is compared ignoring ascii case and trailing dot. Pattern without port
+/// matches host with any port whereas pattern with port only matches that
+/// port.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{HostGlob, Matcher as _};
+///
+/// let matcher = HostGlob::new("*.example.com").unwrap();
+/// assert!(matcher.matches_value("api.example.com"));
+/// assert!(!matcher.matches_value("a.b.example.com"));
+///
+/// let matcher = HostGlob::new("**.example.com").unwrap();
+/// assert!(matcher.matches_value("a.b.example.com"));
+/// assert!(!matcher.matches_value("example.com"));
+/// ```
+#[derive(Clone)]
+pub struct HostGlob {
+    labels: Vec<Label>,
+    port: Option<String>,
+}
+
+impl HostGlob {
+    /// Create new host glob matcher from provided pattern
+    ///
+    /// # Errors
+    /// Returns [`PatternError::InvalidPattern`] if pattern contains empty
+    /// label, `**` which is not a whole label or port which is not a number
+    /// between 0 and 65535
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        let invalid_pattern = || PatternError::InvalidPattern(pattern.to_string());
+        let (name, port) = split_port(pattern);
+        if let Some(port) = port {
+            validate_port(port).map_err(|_| invalid_pattern())?;
+        }
+        let name = name.strip_suffix('.').unwrap_or(name);
+        let labels = name
+            .split('.')
+            .map(|label| {
+                match label {
+                    "" => Err(invalid_pattern()),
+                    "**" => Ok(Label::Many),
+                    _ if label.contains("**") => Err(invalid_pattern()),
+                    _ => Ok(Label::Glob(label.to_ascii_lowercase().chars().collect())),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            labels,
+            port: port.map(ToString::to_string),
+        })
+    }
+}
+
+/// Match labels of host against labels of pattern
+///
+/// Set of label positions reachable after each pattern label is tracked so
+/// consecutive or repeated `**` doesn't cause exponential backtracking.
+fn match_labels(patterns: &[Label], labels: &[&str]) -> bool {
+    let mut reachable = vec![false; labels.len() + 1];
+    reachable[0] = true;
+    for pattern in patterns {
+        let mut next = vec![false; labels.len() + 1];
+        match pattern {
+            Label::Many => {
+                // one or more labels after first reachable position
+                if let Some(first) = reachable.iter().position(|&reached| reached) {
+                    next.iter_mut()
+                        .skip(first + 1)
+                        .for_each(|reached| *reached = true);
+                }
+            }
+            Label::Glob(glob) => {
+                for (index, label) in labels.iter().enumerate() {
+                    next[index + 1] = reachable[index] && match_label(glob, label);
+                }
+            }
+        }
+        reachable = next;
+    }
+    reachable[labels.len()]
+}
+
+/// Match single label against glob containing `*` and `?`
+fn match_label(glob: &[char], label: &str) -> bool {
+    let label = label.chars().collect::<Vec<_>>();
+    let (mut glob_index, mut label_index) = (0, 0);
+    let mut backtrack = None;
+    while label_index < label.len() {
+        match glob.get(glob_index) {
+            Some('*') => {
+                backtrack = Some((glob_index, label_index));
+                glob_index += 1;
+            }
+            Some(&c) if c == '?' || c == label[label_index].to_ascii_lowercase() => {
+                glob_index += 1;
+                label_index += 1;
+            }
+            _ => {
+                let Some((star_index, star_label_index)) = backtrack else {
+                    return false;
+                };
+                backtrack = Some((star_index, star_label_index + 1));
+                glob_index = star_index + 1;
+                label_index = star_label_index + 1;
+            }
+        }
+    }
+    glob[glob_index..].iter().all(|&c| c == '*')
+}
+
+impl Matcher for HostGlob {
+    fn matches_value(&self, value: &str) -> bool {
+        let (hostname, port) = split_port(value);
+        if self.port.is_some() && self.port.as_deref() != port {
+            return false;
+        }
+        let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+        let labels = hostname.split('.').collect::<Vec<_>>();
+        if labels.iter().any(|label| label.is_empty()) {
+            return false;
+        }
+        match_labels(&self.labels, &labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matcher::{HostGlob, Matcher as _};
+
+    #[test]
+    fn host_glob_single_label() {
+        let matcher = HostGlob::new("*.Example.com").unwrap();
+        assert!(matcher.matches_value("api.example.com"));
+        assert!(matcher.matches_value("API.EXAMPLE.COM:8080"));
+        assert!(!matcher.matches_value("example.com"));
+        assert!(!matcher.matches_value(".example.com"));
+        assert!(!matcher.matches_value("a.b.example.com"));
+        assert!(!matcher.matches_value("api.example.com.evil.net"));
+    }
+
+    #[test]
+    fn host_glob_multiple_labels() {
+        let matcher = HostGlob::new("**.example.com").unwrap();
+        assert!(matcher.matches_value("a.example.com"));
+        assert!(matcher.matches_value("a.b.c.example.com"));
+        assert!(!matcher.matches_value("example.com"));
+        assert!(!matcher.matches_value("a..example.com"));
+
+        let matcher = HostGlob::new("api.**.internal").unwrap();
+        assert!(matcher.matches_value("api.a.b.internal"));
+        assert!(!matcher.matches_value("api.internal"));
+    }
+
+    #[test]
+    fn host_glob_within_label() {
+        let matcher = HostGlob::new("api-*.example.com").unwrap();
+        assert!(matcher.matches_value("api-.example.com"));
+        assert!(matcher.matches_value("api-eu.example.com"));
+        assert!(!matcher.matches_value("api-eu.x.example.com"));
+        assert!(!matcher.matches_value("web.example.com"));
+
+        let matcher = HostGlob::new("127.0.0.?").unwrap();
+        assert!(matcher.matches_value("127.0.0.1"));
+        assert!(!matcher.matches_value("127.0.0.20"));
+        assert!(!matcher.matches_value("127.0.0.."));
+        assert!(!matcher.matches_value("127.0.0."));
+
+        let matcher = HostGlob::new("a*b*c").unwrap();
+        assert!(matcher.matches_value("abc"));
+        assert!(matcher.matches_value("axxbyyc"));
+        assert!(!matcher.matches_value("axxbyy"));
+    }
+
+    #[test]
+    fn host_glob_unicode_label() {
+        let matcher = HostGlob::new("m?nchen.de").unwrap();
+        assert!(matcher.matches_value("münchen.de"));
+        assert!(matcher.matches_value("munchen.de"));
+        assert!(!matcher.matches_value("mnchen.de"));
+
+        let matcher = HostGlob::new("??.example.com").unwrap();
+        assert!(matcher.matches_value("日本.example.com"));
+        assert!(!matcher.matches_value("日本語.example.com"));
+    }
+
+    #[test]
+    fn host_glob_repeated_many() {
+        let matcher = HostGlob::new("**.**.example.com").unwrap();
+        assert!(matcher.matches_value("a.b.example.com"));
+        assert!(!matcher.matches_value("a.example.com"));
+
+        let pattern = format!("{}x", "**.".repeat(30));
+        let host = format!("{}y", "a.".repeat(40));
+        assert!(!HostGlob::new(&pattern).unwrap().matches_value(&host));
+
+        let matcher = HostGlob::new("**.a.**.b").unwrap();
+        assert!(matcher.matches_value("x.a.y.z.b"));
+        assert!(!matcher.matches_value("a.y.b"));
+        assert!(!matcher.matches_value("x.a.b"));
+    }
+
+    #[test]
+    fn host_glob_port() {
+        let matcher = HostGlob::new("*.example.com:8080").unwrap();
+        assert!(matcher.matches_value("api.example.com:8080"));
+        assert!(!matcher.matches_value("api.example.com"));
+        assert!(!matcher.matches_value("api.example.com:8081"));
+    }
+
+    #[test]
+    fn host_glob_invalid_pattern() {
+        assert!(HostGlob::new("").is_err());
+        assert!(HostGlob::new("a..example.com").is_err());
+        assert!(HostGlob::new("a**.example.com").is_err());
+        assert!(HostGlob::new("*.example.com:abc").is_err());
+        assert!(HostGlob::new("*.example.com:99999").is_err());
+        assert!(HostGlob::new("*.example.com:").is_err());
+        assert!(HostGlob::new("*.example.com:443").is_ok());
+    }
+}
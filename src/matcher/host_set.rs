@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::error::PatternError;
+use crate::host::{split_port, to_lowercase, validate_port};
+use crate::matcher::Matcher;
+
+/// Matcher which matches host against a set of exact hosts
+///
+/// Lookup is done using hash set so matching cost doesn't depend on number of
+/// hosts. Hosts are compared ignoring ascii case and trailing dot. Host
+/// inserted without port matches any valid port whereas host inserted with
+/// port only matches that port. This differs from `&str` matcher where
+/// `"example.com"` only matches host without port. Host whose port is not a
+/// number between 0 and 65535 is never matched. Host inserted with invalid port
+/// is ignored unless it is inserted using [`HostSet::try_insert`] or
+/// [`HostSet::try_from_iter`].
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{HostSet, Matcher as _};
+///
+/// let matcher = HostSet::from_iter(["example.com", "api.example.com:8443"]);
+/// assert!(matcher.matches_value("Example.COM:8080"));
+/// assert!(matcher.matches_value("api.example.com:8443"));
+/// assert!(!matcher.matches_value("api.example.com"));
+/// ```
+#[derive(Clone, Default)]
+pub struct HostSet {
+    /// Map of hostname to allowed ports where `None` allows any port
+    hosts: HashMap<String, Option<HashSet<u16>>>,
+}
+
+impl HostSet {
+    /// Create new empty host set
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create host set from provided hosts
+    ///
+    /// # Errors
+    /// Returns [`PatternError::InvalidPattern`] if any of host contains invalid
+    /// port
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::matcher::HostSet;
+    ///
+    /// assert!(HostSet::try_from_iter(["example.com:8080"]).is_ok());
+    /// assert!(HostSet::try_from_iter(["example.com:http"]).is_err());
+    /// ```
+    pub fn try_from_iter<I>(hosts: I) -> Result<Self, PatternError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut host_set = Self::new();
+        for host in hosts {
+            host_set.try_insert(host.as_ref())?;
+        }
+        Ok(host_set)
+    }
+
+    /// Insert host into host set
+    ///
+    /// Host with invalid port is ignored. Use [`HostSet::try_insert`] to detect
+    /// such host.
+    pub fn insert(&mut self, host: &str) {
+        let _ = self.try_insert(host);
+    }
+
+    /// Insert host into host set
+    ///
+    /// # Errors
+    /// Returns [`PatternError::InvalidPattern`] if host contains port which is
+    /// not a number between 0 and 65535
+    pub fn try_insert(&mut self, host: &str) -> Result<(), PatternError> {
+        let lowercase_host = host.to_ascii_lowercase();
+        let (hostname, port) = split_port(&lowercase_host);
+        let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+        match port {
+            None => {
+                self.hosts.insert(hostname.to_string(), None);
+            }
+            Some(port) => {
+                let port = validate_port(port)
+                    .ok()
+                    .and_then(|()| port.parse::<u16>().ok())
+                    .ok_or_else(|| PatternError::InvalidPattern(host.to_string()))?;
+                if let Some(ports) = self
+                    .hosts
+                    .entry(hostname.to_string())
+                    .or_insert_with(|| Some(HashSet::new()))
+                {
+                    ports.insert(port);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return number of hostname present in host set
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.hosts.len()
+    }
+
+    /// Check if host set is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+}
+
+impl<T> Extend<T> for HostSet
+where
+    T: AsRef<str>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for host in iter {
+            self.insert(host.as_ref());
+        }
+    }
+}
+
+impl<T> FromIterator<T> for HostSet
+where
+    T: AsRef<str>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut host_set = Self::new();
+        host_set.extend(iter);
+        host_set
+    }
+}
+
+impl Matcher for HostSet {
+    fn matches_value(&self, value: &str) -> bool {
+        let value = to_lowercase(value);
+        let (hostname, port) = split_port(&value);
+        // host with invalid port is never matched even by host without port
+        let port = match port {
+            Some(port) => {
+                let Some(port) = validate_port(port)
+                    .ok()
+                    .and_then(|()| port.parse::<u16>().ok())
+                else {
+                    return false;
+                };
+                Some(port)
+            }
+            None => None,
+        };
+        let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+        match self.hosts.get(hostname) {
+            Some(None) => true,
+            Some(Some(ports)) => port.is_some_and(|port| ports.contains(&port)),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::PatternError;
+    use crate::matcher::{HostSet, Matcher as _};
+
+    #[test]
+    fn host_set_matches_case_insensitive() {
+        let matcher = HostSet::from_iter(["Example.com", "foo.example.com."]);
+        assert_eq!(matcher.len(), 2);
+        assert!(matcher.matches_value("example.com"));
+        assert!(matcher.matches_value("EXAMPLE.COM"));
+        assert!(matcher.matches_value("foo.example.com."));
+        assert!(!matcher.matches_value("bar.example.com"));
+    }
+
+    #[test]
+    fn host_set_port_aware() {
+        let mut matcher = HostSet::new();
+        matcher.insert("example.com:8080");
+        matcher.insert("[::1]:443");
+        matcher.insert("any.example.com");
+        matcher.insert("invalid.example.com:port");
+        assert!(matcher.matches_value("example.com:8080"));
+        assert!(!matcher.matches_value("example.com:8081"));
+        assert!(!matcher.matches_value("example.com"));
+        assert!(matcher.matches_value("[::1]:443"));
+        assert!(!matcher.matches_value("[::1]"));
+        assert!(matcher.matches_value("any.example.com:1234"));
+        assert!(!matcher.matches_value("invalid.example.com"));
+
+        matcher.insert("example.com");
+        assert!(matcher.matches_value("example.com:9000"));
+    }
+
+    #[test]
+    fn host_set_invalid_port() {
+        let mut matcher = HostSet::new();
+        assert!(matcher.try_insert("example.com:443").is_ok());
+        for host in ["example.com:port", "example.com:65536", "example.com:+80"] {
+            assert!(matches!(
+                matcher.try_insert(host),
+                Err(PatternError::InvalidPattern(pattern)) if pattern == host
+            ));
+        }
+        assert_eq!(matcher.len(), 1);
+        assert!(!matcher.matches_value("example.com:65536"));
+
+        let matcher = HostSet::from_iter(["example.com"]);
+        assert!(matcher.matches_value("example.com:8080"));
+        for host in [
+            "example.com:abc",
+            "example.com:+80",
+            "example.com:",
+            "example.com:65536",
+        ] {
+            assert!(!matcher.matches_value(host), "{host}");
+        }
+
+        assert!(HostSet::try_from_iter(["a.com", "b.com:8080"]).is_ok());
+        assert!(HostSet::try_from_iter(["a.com", "b.com:port"]).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::error::PatternError;
+use crate::host::{split_port, to_lowercase};
+use crate::matcher::Matcher;
+
+/// Node of reversed label trie
+#[derive(Clone, Default)]
+struct Node {
+    children: HashMap<String, Node>,
+    /// node matches host with exactly same labels
+    exact: bool,
+    /// node matches host with exactly one more label
+    wildcard: bool,
+    /// node matches host with same labels or any number of more labels
+    suffix: bool,
+}
+
+/// Matcher which compiles exact, wildcard and suffix rules into a trie of
+/// reversed labels
+///
+/// Matching cost depends on number of labels present in host rather than
+/// number of rules. Supported rules are
+/// - `example.com` which matches only `example.com`
+/// - `*.example.com` which matches host with exactly one label before
+///   `example.com` such as `api.example.com`
+/// - `.example.com` which matches `example.com` and all of its subdomains
+///
+/// Hosts are compared ignoring ascii case, port and trailing dot so rule with
+/// port is rejected. Use `psl::check_pattern` with `psl` feature to verify that
+/// rules doesn't cover public suffix.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{HostTrie, Matcher as _};
+///
+/// let matcher = HostTrie::from_rules(["example.com", "*.tenant.example.com"]).unwrap();
+/// assert!(matcher.matches_value("example.com"));
+/// assert!(matcher.matches_value("foo.tenant.example.com:8080"));
+/// assert!(!matcher.matches_value("foo.bar.tenant.example.com"));
+/// ```
+#[derive(Clone, Default)]
+pub struct HostTrie {
+    root: Node,
+}
+
+impl HostTrie {
+    /// Create new empty host trie
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create host trie from provided rules
+    ///
+    /// # Errors
+    /// Returns [`PatternError::InvalidPattern`] if any of rule is invalid
+    pub fn from_rules<I>(rules: I) -> Result<Self, PatternError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut trie = Self::new();
+        for rule in rules {
+            trie.insert(rule.as_ref())?;
+        }
+        Ok(trie)
+    }
+
+    /// Insert rule into host trie
+    ///
+    /// # Errors
+    /// Returns [`PatternError::InvalidPattern`] if rule contains empty label,
+    /// wildcard in position other than first label or port
+    pub fn insert(&mut self, rule: &str) -> Result<(), PatternError> {
+        let lowercase_rule = rule.to_ascii_lowercase();
+        // port of host is ignored while matching so rule with port would never
+        // match
+        let (name, port) = split_port(&lowercase_rule);
+        if port.is_some() || (!name.starts_with('[') && name.contains(':')) {
+            return Err(PatternError::InvalidPattern(rule.to_string()));
+        }
+        let name = name.strip_suffix('.').unwrap_or(name);
+        if name == "*" {
+            self.root.wildcard = true;
+            return Ok(());
+        }
+        let (name, set_flag): (&str, fn(&mut Node)) = if let Some(name) = name.strip_prefix('.') {
+            (name, |node| node.suffix = true)
+        } else if let Some(name) = name.strip_prefix("*.") {
+            (name, |node| node.wildcard = true)
+        } else {
+            (name, |node| node.exact = true)
+        };
+        let mut node = &mut self.root;
+        for label in name.rsplit('.') {
+            if label.is_empty() || label.contains('*') {
+                return Err(PatternError::InvalidPattern(rule.to_string()));
+            }
+            node = node.children.entry(label.to_string()).or_default();
+        }
+        set_flag(node);
+        Ok(())
+    }
+}
+
+impl Matcher for HostTrie {
+    fn matches_value(&self, value: &str) -> bool {
+        let value = to_lowercase(value);
+        let (hostname, _) = split_port(&value);
+        let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+        let mut node = &self.root;
+        let mut labels = hostname.rsplit('.').peekable();
+        while let Some(label) = labels.next() {
+            if label.is_empty() {
+                return false;
+            }
+            if node.suffix || (node.wildcard && labels.peek().is_none()) {
+                return true;
+            }
+            match node.children.get(label) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        node.exact || node.suffix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matcher::{HostTrie, Matcher as _};
+
+    #[test]
+    fn host_trie_rules() {
+        let matcher =
+            HostTrie::from_rules(["Example.com", "*.tenant.example.com", ".suffix.example.org"])
+                .unwrap();
+        assert!(matcher.matches_value("example.com"));
+        assert!(matcher.matches_value("EXAMPLE.com.:443"));
+        assert!(!matcher.matches_value("api.example.com"));
+        assert!(matcher.matches_value("a.tenant.example.com"));
+        assert!(!matcher.matches_value("tenant.example.com"));
+        assert!(!matcher.matches_value("a.b.tenant.example.com"));
+        assert!(matcher.matches_value("suffix.example.org"));
+        assert!(matcher.matches_value("a.b.suffix.example.org"));
+        assert!(!matcher.matches_value("example.org"));
+        assert!(!matcher.matches_value("a..tenant.example.com"));
+        assert!(!matcher.matches_value(""));
+    }
+
+    #[test]
+    fn host_trie_invalid_rules() {
+        assert!(HostTrie::from_rules(["a.*.example.com"]).is_err());
+        assert!(HostTrie::from_rules(["a..example.com"]).is_err());
+        assert!(HostTrie::from_rules(["."]).is_err());
+        assert!(HostTrie::from_rules([""]).is_err());
+        assert!(HostTrie::from_rules(["*.*.example.com"]).is_err());
+        assert!(HostTrie::from_rules(["example.com:443"]).is_err());
+        assert!(HostTrie::from_rules(["*.example.com:8080"]).is_err());
+        assert!(HostTrie::from_rules(["[::1]:443"]).is_err());
+        assert!(HostTrie::from_rules(["::1"]).is_err());
+        assert!(
+            HostTrie::from_rules(["[::1]"])
+                .unwrap()
+                .matches_value("[::1]:443")
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use regex::RegexSet;
+
+use crate::matcher::Matcher;
+
+/// Matcher which compiles multiple regex patterns into a single regex set
+///
+/// All patterns are anchored automatically so pattern must match whole host.
+/// Host matches when any of pattern matches.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{Matcher as _, RegexHosts};
+///
+/// let matcher = RegexHosts::new([r"[a-z]+\.example\.com", r"127\.0\.0\.\d+"]).unwrap();
+/// assert!(matcher.matches_value("api.example.com"));
+/// assert!(!matcher.matches_value("api.example.com.evil.net"));
+/// assert_eq!(matcher.matching_patterns("127.0.0.1"), vec![1]);
+/// ```
+#[derive(Clone)]
+pub struct RegexHosts {
+    patterns: Vec<String>,
+    set: RegexSet,
+}
+
+impl RegexHosts {
+    /// Create new regex hosts matcher from provided patterns
+    ///
+    /// # Errors
+    /// Returns error if any of pattern is invalid regex
+    pub fn new<I>(patterns: I) -> Result<Self, regex::Error>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| pattern.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let set = RegexSet::new(patterns.iter().map(|pattern| format!("^(?:{pattern})$")))?;
+        Ok(Self { patterns, set })
+    }
+
+    /// Return indices of patterns which matches value
+    #[must_use]
+    pub fn matching_patterns(&self, value: &str) -> Vec<usize> {
+        self.set.matches(value).into_iter().collect()
+    }
+
+    /// Return patterns used to create matcher without added anchors
+    #[must_use]
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+}
+
+impl Matcher for RegexHosts {
+    fn matches_value(&self, value: &str) -> bool {
+        self.set.is_match(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matcher::{Matcher as _, RegexHosts};
+
+    #[test]
+    fn regex_hosts_anchored() {
+        let matcher = RegexHosts::new(["example.com", r"[a-z]+\.example\.org"]).unwrap();
+        assert!(matcher.matches_value("example.com"));
+        assert!(!matcher.matches_value("example.com.evil.net"));
+        assert!(!matcher.matches_value("evil.example.com"));
+        assert!(matcher.matches_value("api.example.org"));
+        assert!(!matcher.matches_value("api1.example.org"));
+    }
+
+    #[test]
+    fn regex_hosts_matching_patterns() {
+        let matcher = RegexHosts::new([r".*\.example\.com", r"api\..*", "a|b"]).unwrap();
+        assert_eq!(matcher.matching_patterns("api.example.com"), vec![0, 1]);
+        assert_eq!(matcher.matching_patterns("ab"), Vec::<usize>::new());
+        assert_eq!(matcher.matching_patterns("b"), vec![2]);
+        assert_eq!(matcher.patterns().len(), 3);
+        assert!(RegexHosts::new(["("]).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use crate::matcher::{Decision, Matcher};
+
+/// Action taken by rule when its matcher matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Action {
+    /// allow host
+    Allow,
+    /// deny host
+    Deny,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allow => write!(f, "allow"),
+            Self::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+/// Precedence used by [`RuleSet`] to decide between matching rules
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Precedence {
+    /// first matching rule in order decides outcome
+    #[default]
+    FirstMatch,
+    /// any matching deny rule decides outcome even if allow rule matches
+    /// before it
+    DenyOverrides,
+}
+
+/// Single rule of rule set
+#[derive(Clone)]
+struct Rule {
+    action: Action,
+    name: Arc<str>,
+    matcher: Arc<dyn Matcher + Send + Sync>,
+}
+
+impl Rule {
+    /// Create decision using rule name along with label reported by matcher
+    fn decision(&self, label: Option<Arc<str>>) -> Decision {
+        let decision = Decision::new(self.action == Action::Allow);
+        match label {
+            Some(label) => decision.with_rule(format!("{} ({label})", self.name)),
+            None => decision.with_rule(Arc::clone(&self.name)),
+        }
+    }
+}
+
+/// Matcher which contains ordered list of allow and deny rules
+///
+/// Host which doesn't match any rule is denied. Deciding rule is reported in
+/// rejection error as `allow rule #index` or `deny rule #index` where index
+/// starts from 0. Label of matcher created using `with_label` is appended to
+/// rule name.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::{HostGlob, Matcher as _, MatcherExt as _, RuleSet};
+///
+/// let rule_set = RuleSet::new()
+///     .deny("internal.example.com".with_label("internal"))
+///     .deny(HostGlob::new("*.staging.example.com").unwrap())
+///     .allow(HostGlob::new("**.example.com").unwrap());
+/// assert!(rule_set.matches_value("api.example.com"));
+/// assert!(!rule_set.matches_value("internal.example.com"));
+/// assert!(!rule_set.matches_value("api.staging.example.com"));
+/// assert_eq!(
+///     rule_set.decide("internal.example.com").rule.as_deref(),
+///     Some("deny rule #0 (internal)")
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    precedence: Precedence,
+}
+
+impl RuleSet {
+    /// Create new empty rule set with first match precedence
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set precedence of rule set
+    #[must_use]
+    pub fn with_precedence(self, precedence: Precedence) -> Self {
+        Self { precedence, ..self }
+    }
+
+    /// Append allow rule
+    #[must_use]
+    pub fn allow<M>(self, matcher: M) -> Self
+    where
+        M: Matcher + Send + Sync + 'static,
+    {
+        self.rule(Action::Allow, matcher)
+    }
+
+    /// Append deny rule
+    #[must_use]
+    pub fn deny<M>(self, matcher: M) -> Self
+    where
+        M: Matcher + Send + Sync + 'static,
+    {
+        self.rule(Action::Deny, matcher)
+    }
+
+    /// Append rule with provided action
+    #[must_use]
+    pub fn rule<M>(mut self, action: Action, matcher: M) -> Self
+    where
+        M: Matcher + Send + Sync + 'static,
+    {
+        self.rules.push(Rule {
+            action,
+            name: format!("{action} rule #{}", self.rules.len()).into(),
+            matcher: Arc::new(matcher),
+        });
+        self
+    }
+
+    /// Find rule which decides outcome for value
+    fn find_decision(&self, value: &str) -> Option<Decision> {
+        let mut matching_rules = self.rules.iter().filter_map(|rule| {
+            let decision = rule.matcher.decide(value);
+            decision.allowed.then(|| rule.decision(decision.rule))
+        });
+        match self.precedence {
+            Precedence::FirstMatch => matching_rules.next(),
+            Precedence::DenyOverrides => {
+                let mut first_allow = None;
+                for decision in matching_rules {
+                    if !decision.allowed {
+                        return Some(decision);
+                    }
+                    first_allow.get_or_insert(decision);
+                }
+                first_allow
+            }
+        }
+    }
+}
+
+impl Matcher for RuleSet {
+    fn matches_value(&self, value: &str) -> bool {
+        self.decide(value).allowed
+    }
+
+    fn decide(&self, value: &str) -> Decision {
+        let decision = self
+            .find_decision(value)
+            .unwrap_or_else(|| Decision::new(false));
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            "rule set {} host {} by {}",
+            if decision.allowed {
+                "allowed"
+            } else {
+                "denied"
+            },
+            value,
+            decision.rule.as_deref().unwrap_or("no matching rule")
+        );
+        decision
+    }
+
+    fn check_config(&self) {
+        for rule in &self.rules {
+            rule.matcher.check_config();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matcher::{HostGlob, Matcher as _, MatcherExt as _, Precedence, RuleSet};
+
+    #[test]
+    fn rule_set_first_match() {
+        let rule_set = RuleSet::new()
+            .deny("internal.example.com")
+            .allow(HostGlob::new("*.example.com").unwrap())
+            .deny("api.example.com");
+        assert!(rule_set.matches_value("api.example.com"));
+        assert!(!rule_set.matches_value("internal.example.com"));
+        assert!(!rule_set.matches_value("example.org"));
+        assert_eq!(
+            rule_set.decide("api.example.com").rule.as_deref(),
+            Some("allow rule #1")
+        );
+        assert_eq!(
+            rule_set.decide("internal.example.com").rule.as_deref(),
+            Some("deny rule #0")
+        );
+        assert_eq!(rule_set.decide("example.org").rule, None);
+    }
+
+    #[test]
+    fn rule_set_deny_overrides() {
+        let rule_set = RuleSet::new()
+            .with_precedence(Precedence::DenyOverrides)
+            .allow(HostGlob::new("**.example.com").unwrap())
+            .deny(
+                HostGlob::new("*.staging.example.com")
+                    .unwrap()
+                    .with_label("staging"),
+            );
+        assert!(rule_set.matches_value("api.example.com"));
+        assert!(!rule_set.matches_value("api.staging.example.com"));
+        assert_eq!(
+            rule_set.decide("api.staging.example.com").rule.as_deref(),
+            Some("deny rule #1 (staging)")
+        );
+        assert!(!RuleSet::new().matches_value("example.com"));
+    }
+}
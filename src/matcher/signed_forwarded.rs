@@ -0,0 +1,297 @@
+use std::fmt::Write as _;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac as _};
+use sha2::Sha256;
+
+use crate::forwarded::{self, Parameters};
+use crate::matcher::KeyValueMatcher;
+
+/// HMAC-SHA256 type used for signing
+type HmacSha256 = Hmac<Sha256>;
+
+/// Key value matcher which verifies HMAC-SHA256 signature of `Forwarded`
+/// element
+///
+/// Signature is lowercase hex encoded HMAC-SHA256 of every other parameter of
+/// element in order of appearance serialized as `name=value` joined by `;`,
+/// where value is written as token if possible else as quoted string. For
+/// example signature of `for=192.0.2.43;host=example.com;signature=...` is
+/// computed over `for=192.0.2.43;host=example.com`.
+///
+/// Multiple keys can be active at same time so key can be rotated without
+/// downtime. Element is accepted when signature is valid for any key. When
+/// timestamp parameter is configured, element must contain unix timestamp in
+/// seconds within allowed skew so captured element can't be replayed later.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use tower_allowed_hosts::matcher::SignedForwarded;
+///
+/// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com").with_forwarded_matcher(
+///     SignedForwarded::new(b"current secret")
+///         .with_key(b"previous secret")
+///         .with_timestamp("ts", Duration::from_secs(30)),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct SignedForwarded {
+    keys: Vec<Vec<u8>>,
+    signature_parameter: String,
+    timestamp: Option<(String, Duration)>,
+}
+
+impl SignedForwarded {
+    /// Create new matcher with key used for signing and verification
+    ///
+    /// Signature is read from `signature` parameter.
+    pub fn new<K>(key: K) -> Self
+    where
+        K: AsRef<[u8]>,
+    {
+        Self {
+            keys: vec![key.as_ref().to_vec()],
+            signature_parameter: "signature".to_string(),
+            timestamp: None,
+        }
+    }
+
+    /// Add additional key accepted during verification
+    #[must_use]
+    pub fn with_key<K>(mut self, key: K) -> Self
+    where
+        K: AsRef<[u8]>,
+    {
+        self.keys.push(key.as_ref().to_vec());
+        self
+    }
+
+    /// Set name of parameter which contains signature
+    #[must_use]
+    pub fn with_signature_parameter<T>(mut self, name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.signature_parameter = name.into().to_ascii_lowercase();
+        self
+    }
+
+    /// Require parameter containing unix timestamp in seconds which must be
+    /// within provided skew of current time
+    #[must_use]
+    pub fn with_timestamp<T>(mut self, name: T, skew: Duration) -> Self
+    where
+        T: Into<String>,
+    {
+        self.timestamp = Some((name.into().to_ascii_lowercase(), skew));
+        self
+    }
+
+    /// Compute signature of parameters using first key
+    ///
+    /// Signature parameter is excluded if present so returned value can be
+    /// appended to element by proxy.
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::forwarded::Parameters;
+    /// use tower_allowed_hosts::matcher::{KeyValueMatcher as _, SignedForwarded};
+    ///
+    /// let matcher = SignedForwarded::new(b"secret");
+    /// let mut pairs = vec![("host".to_string(), "example.com".to_string())];
+    /// let signature = matcher.sign(&Parameters::new(&pairs));
+    /// pairs.push(("signature".to_string(), signature));
+    /// assert!(matcher.matches_key_value(&Parameters::new(&pairs)));
+    /// ```
+    #[must_use]
+    pub fn sign(&self, parameters: &Parameters<'_>) -> String {
+        let Some(mac) = self.keys.first().and_then(|key| self.mac(key, parameters)) else {
+            return String::new();
+        };
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .fold(String::new(), |mut output, byte| {
+                let _ = write!(output, "{byte:02x}");
+                output
+            })
+    }
+
+    /// Create HMAC updated with signed content of parameters
+    fn mac(&self, key: &[u8], parameters: &Parameters<'_>) -> Option<HmacSha256> {
+        let mut mac = HmacSha256::new_from_slice(key).ok()?;
+        let content = forwarded::write_pairs(
+            parameters
+                .iter()
+                .filter(|(name, _)| *name != self.signature_parameter),
+        );
+        mac.update(content.as_bytes());
+        Some(mac)
+    }
+
+    /// Check whether timestamp parameter is within allowed skew
+    fn timestamp_valid(&self, parameters: &Parameters<'_>) -> bool {
+        let Some((name, skew)) = &self.timestamp else {
+            return true;
+        };
+        let Some(timestamp) = parameters
+            .get(name)
+            .and_then(|value| value.parse::<u64>().ok())
+        else {
+            return false;
+        };
+        let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) else {
+            return false;
+        };
+        now.as_secs().abs_diff(timestamp) <= skew.as_secs()
+    }
+}
+
+impl std::fmt::Debug for SignedForwarded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SignedForwarded")
+            .field("keys", &self.keys.len())
+            .field("signature_parameter", &self.signature_parameter)
+            .field("timestamp", &self.timestamp)
+            .finish()
+    }
+}
+
+impl KeyValueMatcher for SignedForwarded {
+    fn matches_key_value(&self, values: &Parameters<'_>) -> bool {
+        let Some(signature) = values.get(&self.signature_parameter).and_then(decode_hex) else {
+            return false;
+        };
+        if !self.timestamp_valid(values) {
+            return false;
+        }
+        self.keys.iter().any(|key| {
+            self.mac(key, values)
+                .is_some_and(|mac| mac.verify_slice(&signature).is_ok())
+        })
+    }
+}
+
+/// Decode hex encoded value
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    // from_str_radix also accepts sign so only hex digits are allowed
+    if !value.len().is_multiple_of(2) || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(value.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::forwarded::Parameters;
+    use crate::matcher::signed_forwarded::decode_hex;
+    use crate::matcher::{KeyValueMatcher as _, SignedForwarded};
+
+    fn signed(matcher: &SignedForwarded, pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut pairs = pairs
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect::<Vec<_>>();
+        let signature = matcher.sign(&Parameters::new(&pairs));
+        pairs.push(("signature".to_string(), signature));
+        pairs
+    }
+
+    fn now() -> String {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string()
+    }
+
+    #[test]
+    fn verify_signature() {
+        let matcher = SignedForwarded::new(b"secret");
+        let pairs = signed(&matcher, &[("for", "192.0.2.43"), ("host", "example.com")]);
+        assert!(matcher.matches_key_value(&Parameters::new(&pairs)));
+
+        let mut tampered = pairs.clone();
+        tampered[1].1 = "evil.com".to_string();
+        assert!(!matcher.matches_key_value(&Parameters::new(&tampered)));
+
+        let mut reordered = pairs.clone();
+        reordered.swap(0, 1);
+        assert!(!matcher.matches_key_value(&Parameters::new(&reordered)));
+
+        let unsigned = &pairs[..2];
+        assert!(!matcher.matches_key_value(&Parameters::new(unsigned)));
+
+        let other_key = SignedForwarded::new(b"other");
+        assert!(!other_key.matches_key_value(&Parameters::new(&pairs)));
+    }
+
+    #[test]
+    fn quoted_values_are_not_ambiguous() {
+        let matcher = SignedForwarded::new(b"secret");
+        let pairs = signed(&matcher, &[("host", "a;for=b")]);
+        let mut split = vec![
+            ("host".to_string(), "a".to_string()),
+            ("for".to_string(), "b".to_string()),
+        ];
+        split.push(pairs[1].clone());
+        assert!(matcher.matches_key_value(&Parameters::new(&pairs)));
+        assert!(!matcher.matches_key_value(&Parameters::new(&split)));
+    }
+
+    #[test]
+    fn key_rotation() {
+        let old = SignedForwarded::new(b"old");
+        let new = SignedForwarded::new(b"new");
+        let rotating = SignedForwarded::new(b"new").with_key(b"old");
+        let old_pairs = signed(&old, &[("host", "example.com")]);
+        let new_pairs = signed(&new, &[("host", "example.com")]);
+        assert!(rotating.matches_key_value(&Parameters::new(&old_pairs)));
+        assert!(rotating.matches_key_value(&Parameters::new(&new_pairs)));
+        assert!(!new.matches_key_value(&Parameters::new(&old_pairs)));
+    }
+
+    #[test]
+    fn timestamp_skew() {
+        let matcher = SignedForwarded::new(b"secret")
+            .with_signature_parameter("Sig")
+            .with_timestamp("ts", Duration::from_secs(30));
+        let sign = |pairs: &[(&str, &str)]| {
+            let mut pairs = pairs
+                .iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect::<Vec<_>>();
+            let signature = matcher.sign(&Parameters::new(&pairs));
+            pairs.push(("sig".to_string(), signature));
+            pairs
+        };
+
+        let now = now();
+        let fresh = sign(&[("host", "example.com"), ("ts", &now)]);
+        assert!(matcher.matches_key_value(&Parameters::new(&fresh)));
+
+        let stale = sign(&[("host", "example.com"), ("ts", "1000")]);
+        assert!(!matcher.matches_key_value(&Parameters::new(&stale)));
+
+        let missing = sign(&[("host", "example.com")]);
+        assert!(!matcher.matches_key_value(&Parameters::new(&missing)));
+    }
+
+    #[test]
+    fn hex_decoding() {
+        assert_eq!(decode_hex("00ff10"), Some(vec![0, 255, 16]));
+        assert_eq!(decode_hex("0"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(decode_hex("é0"), None);
+        assert_eq!(decode_hex("+f"), None);
+        assert_eq!(decode_hex("-1"), None);
+        assert_eq!(decode_hex("0+ff"), None);
+    }
+}
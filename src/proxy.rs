@@ -0,0 +1,338 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use http::Extensions;
+
+use crate::error::PatternError;
+
+/// Network of IP addresses written in CIDR notation such as `10.0.0.0/8`
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::proxy::IpNetwork;
+///
+/// let network = "10.0.0.0/8".parse::<IpNetwork>()?;
+/// assert!(network.contains("10.1.2.3".parse()?));
+/// assert!(!network.contains("192.0.2.1".parse()?));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// Create new network from address and prefix length
+    ///
+    /// # Errors
+    /// Returns error if prefix is longer than address
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, PatternError> {
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        if prefix > max_prefix {
+            return Err(PatternError::InvalidPattern(format!("{addr}/{prefix}")));
+        }
+        Ok(Self { addr, prefix })
+    }
+
+    /// Check whether address is part of network
+    ///
+    /// IPv4-mapped IPv6 address is compared as IPv4 address.
+    #[must_use]
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(addr: IpAddr) -> Self {
+        let prefix = if addr.is_ipv4() { 32 } else { 128 };
+        Self { addr, prefix }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = PatternError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || PatternError::InvalidPattern(value.to_string());
+        match value.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr = addr.parse().map_err(|_| invalid())?;
+                let prefix = prefix.parse().map_err(|_| invalid())?;
+                Self::new(addr, prefix)
+            }
+            None => {
+                value
+                    .parse::<IpAddr>()
+                    .map(Self::from)
+                    .map_err(|_| invalid())
+            }
+        }
+    }
+}
+
+/// Handling of forwarded headers sent by peer which is not trusted proxy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UntrustedForwarded {
+    /// forwarded headers are ignored and host is resolved from request
+    #[default]
+    Ignore,
+    /// request containing forwarded headers is rejected
+    Reject,
+}
+
+/// Strategy used to select element of forwarded chain
+///
+/// Each proxy appends element describing request it received so rightmost
+/// element is added by proxy closest to service. Host of selected element is
+/// only used when element is also accepted by forwarded matcher.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HopSelection {
+    /// leftmost element which contains host and is accepted by forwarded
+    /// matcher
+    #[default]
+    FirstMatch,
+    /// rightmost element
+    Rightmost,
+    /// element added by outermost of provided number of trusted proxies which
+    /// is nth element from right, `1` is same as [`HopSelection::Rightmost`].
+    /// No element is selected when chain is shorter or number is `0`
+    TrustedHops(usize),
+    /// element found by walking chain from right to left starting from peer
+    /// address of connection. Rightmost element is trusted when peer is part of
+    /// [`TrustedProxies`] and element on its left is trusted when `for` of
+    /// trusted element is part of [`TrustedProxies`]. Leftmost trusted element
+    /// is selected. `by` is not used since it is reported by element itself.
+    /// No element is selected when [`TrustedProxies`] is not configured
+    TrustedChain,
+}
+
+/// Header family used first when both `Forwarded` and `X-Forwarded-Host`
+/// headers are present
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ForwardedPrecedence {
+    /// `Forwarded` header is used and `X-Forwarded-*` headers are only used
+    /// when it doesn't provide host
+    #[default]
+    Forwarded,
+    /// `X-Forwarded-*` headers are used and `Forwarded` header is only used
+    /// when they don't provide host
+    XForwarded,
+}
+
+/// Configuration of legacy `X-Forwarded-Host`, `X-Forwarded-Proto`,
+/// `X-Forwarded-Port` and `X-Forwarded-For` headers
+///
+/// Each header contains comma separated list of values where each proxy
+/// appends its value, so lists are aligned from right to form one hop per value
+/// of `X-Forwarded-Host`. Hop is selected according to [`HopSelection`] where
+/// [`HopSelection::FirstMatch`] selects leftmost hop and
+/// [`HopSelection::TrustedChain`] walks `X-Forwarded-For` values. When selected
+/// hop has port and host doesn't contain port, port is appended to host unless
+/// it is default port of proto.
+///
+/// Headers contain no parameter which can be verified so they are ignored
+/// unless [`TrustedProxies`] is configured. Port which is not number between
+/// 0 and 65535 is rejected with
+/// [`InvalidHostKind::InvalidPort`](crate::error::InvalidHostKind::InvalidPort).
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::proxy::{ForwardedPrecedence, HopSelection, XForwarded};
+///
+/// let x_forwarded = XForwarded::new()
+///     .with_hop_selection(HopSelection::TrustedHops(2))
+///     .with_precedence(ForwardedPrecedence::XForwarded);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XForwarded {
+    hop_selection: HopSelection,
+    precedence: ForwardedPrecedence,
+}
+
+impl XForwarded {
+    /// Create new configuration using [`HopSelection::Rightmost`] and
+    /// [`ForwardedPrecedence::Forwarded`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            hop_selection: HopSelection::Rightmost,
+            precedence: ForwardedPrecedence::default(),
+        }
+    }
+
+    /// Set strategy used to select hop
+    #[must_use]
+    pub fn with_hop_selection(mut self, hop_selection: HopSelection) -> Self {
+        self.hop_selection = hop_selection;
+        self
+    }
+
+    /// Set header family used first when both are present
+    #[must_use]
+    pub fn with_precedence(mut self, precedence: ForwardedPrecedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
+
+    /// Get strategy used to select hop
+    pub(crate) fn hop_selection(&self) -> HopSelection {
+        self.hop_selection
+    }
+
+    /// Get header family used first
+    pub(crate) fn precedence(&self) -> ForwardedPrecedence {
+        self.precedence
+    }
+}
+
+impl Default for XForwarded {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Set of proxy networks whose forwarded headers are trusted
+///
+/// Peer address is read from [`SocketAddr`] extension of request or from
+/// `axum::extract::ConnectInfo<SocketAddr>` extension when `axum`
+/// feature is enabled. Request whose peer address is unknown is considered
+/// untrusted.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::proxy::{TrustedProxies, UntrustedForwarded};
+///
+/// let trusted_proxies = TrustedProxies::new(["10.0.0.0/8".parse()?, "::1".parse()?])
+///     .with_untrusted(UntrustedForwarded::Reject);
+/// # Ok::<(), tower_allowed_hosts::error::PatternError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    networks: Vec<IpNetwork>,
+    untrusted: UntrustedForwarded,
+}
+
+impl TrustedProxies {
+    /// Create new trusted proxies from networks
+    pub fn new<I>(networks: I) -> Self
+    where
+        I: IntoIterator<Item = IpNetwork>,
+    {
+        Self {
+            networks: networks.into_iter().collect(),
+            untrusted: UntrustedForwarded::default(),
+        }
+    }
+
+    /// Set handling of forwarded headers sent by untrusted peer. Default is
+    /// [`UntrustedForwarded::Ignore`]
+    #[must_use]
+    pub fn with_untrusted(mut self, untrusted: UntrustedForwarded) -> Self {
+        self.untrusted = untrusted;
+        self
+    }
+
+    /// Check whether address is part of any trusted network
+    #[must_use]
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(addr))
+    }
+
+    /// Get handling of forwarded headers sent by untrusted peer
+    pub(crate) fn untrusted(&self) -> UntrustedForwarded {
+        self.untrusted
+    }
+}
+
+/// Get peer address of connection from request extensions
+pub(crate) fn peer_addr(extensions: &Extensions) -> Option<IpAddr> {
+    if let Some(addr) = extensions.get::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    #[cfg(feature = "axum")]
+    if let Some(axum::extract::ConnectInfo(addr)) =
+        extensions.get::<axum::extract::ConnectInfo<SocketAddr>>()
+    {
+        return Some(addr.ip());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+    use http::Extensions;
+
+    use crate::proxy::{IpNetwork, TrustedProxies, peer_addr};
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn network_contains() {
+        let network = "10.0.0.0/8".parse::<IpNetwork>().unwrap();
+        assert!(network.contains(ip("10.255.0.1")));
+        assert!(network.contains(ip("::ffff:10.0.0.1")));
+        assert!(!network.contains(ip("11.0.0.1")));
+        assert!(!network.contains(ip("::1")));
+
+        let network = "2001:db8::/32".parse::<IpNetwork>().unwrap();
+        assert!(network.contains(ip("2001:db8:cafe::17")));
+        assert!(!network.contains(ip("2001:db9::1")));
+
+        let single = "192.0.2.1".parse::<IpNetwork>().unwrap();
+        assert!(single.contains(ip("192.0.2.1")));
+        assert!(!single.contains(ip("192.0.2.2")));
+
+        let all = "0.0.0.0/0".parse::<IpNetwork>().unwrap();
+        assert!(all.contains(ip("203.0.113.1")));
+    }
+
+    #[test]
+    fn invalid_network() {
+        for invalid in [
+            "10.0.0.0/33",
+            "::/129",
+            "10.0.0.0/",
+            "example.com/8",
+            "10.0.0/8",
+        ] {
+            assert!(invalid.parse::<IpNetwork>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn trusted_proxies_and_peer() {
+        let trusted_proxies =
+            TrustedProxies::new(["10.0.0.0/8".parse().unwrap(), "::1".parse().unwrap()]);
+        assert!(trusted_proxies.contains(ip("10.1.1.1")));
+        assert!(trusted_proxies.contains(ip("::1")));
+        assert!(!trusted_proxies.contains(ip("192.0.2.1")));
+
+        let mut extensions = Extensions::new();
+        assert_eq!(peer_addr(&extensions), None);
+        extensions.insert(SocketAddr::from((Ipv4Addr::new(10, 0, 0, 1), 4711)));
+        assert_eq!(peer_addr(&extensions), Some(ip("10.0.0.1")));
+    }
+}
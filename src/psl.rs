@@ -0,0 +1,242 @@
+use std::net::IpAddr;
+
+use crate::error::PatternError;
+use crate::host::hostname;
+use crate::matcher::Matcher;
+
+/// Generated public suffix list rules
+mod list;
+
+/// Check if sorted rules contains value while ignoring ascii case of value
+fn contains(rules: &[&str], value: &str) -> bool {
+    rules
+        .binary_search_by(|rule| {
+            rule.bytes()
+                .cmp(value.bytes().map(|b| b.to_ascii_lowercase()))
+        })
+        .is_ok()
+}
+
+/// Return public suffix of a domain
+///
+/// Returns `None` for empty value or IP address. Domain which doesn't match any
+/// rule uses default `*` rule so its last label is returned.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::psl::public_suffix;
+///
+/// assert_eq!(public_suffix("www.example.co.uk"), Some("co.uk"));
+/// assert_eq!(public_suffix("127.0.0.1"), None);
+/// ```
+#[must_use]
+pub fn public_suffix(domain: &str) -> Option<&str> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    if domain.is_empty() || domain.starts_with('[') || domain.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    let mut candidate = domain;
+    loop {
+        let parent = candidate.split_once('.').map(|(_, parent)| parent);
+        if contains(list::EXCEPTION_RULES, candidate) {
+            return parent;
+        }
+        if contains(list::RULES, candidate)
+            || parent.is_some_and(|parent| contains(list::WILDCARD_RULES, parent))
+        {
+            return Some(candidate);
+        }
+        match parent {
+            Some(parent) => candidate = parent,
+            None => return Some(candidate),
+        }
+    }
+}
+
+/// Check if domain is itself a public suffix
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::psl::is_public_suffix;
+///
+/// assert!(is_public_suffix("co.uk"));
+/// assert!(!is_public_suffix("example.co.uk"));
+/// ```
+#[must_use]
+pub fn is_public_suffix(domain: &str) -> bool {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    public_suffix(domain).is_some_and(|suffix| suffix.len() == domain.len())
+}
+
+/// Return registrable domain of a domain which is public suffix along with one
+/// more label
+///
+/// Return None when domain is a public suffix or label in front of suffix is
+/// empty.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::psl::registrable_domain;
+///
+/// assert_eq!(
+///     registrable_domain("www.example.co.uk"),
+///     Some("example.co.uk")
+/// );
+/// assert_eq!(registrable_domain("co.uk"), None);
+/// ```
+#[must_use]
+pub fn registrable_domain(domain: &str) -> Option<&str> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    let suffix = public_suffix(domain)?;
+    let prefix = domain
+        .len()
+        .checked_sub(suffix.len() + 1)
+        .map(|end| &domain[..end])?;
+    let start = prefix.rfind('.').map_or(0, |index| index + 1);
+    if start == prefix.len() {
+        return None;
+    }
+    Some(&domain[start..])
+}
+
+/// Check that wildcard or suffix pattern doesn't cover a public suffix
+///
+/// Pattern is considered a wildcard pattern when any of its label contains `*`
+/// or `?` and a suffix pattern when it starts with `.`. Labels right to the
+/// last wildcard label (or whole suffix pattern) must not be empty or a public
+/// suffix. Pattern without wildcard or leading `.` is always accepted.
+///
+/// # Errors
+/// Returns [`PatternError::PublicSuffix`] when pattern covers public suffix
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::psl::check_pattern;
+///
+/// assert!(check_pattern("*.example.co.uk").is_ok());
+/// assert!(check_pattern("*.co.uk").is_err());
+/// assert!(check_pattern(".com").is_err());
+/// ```
+pub fn check_pattern(pattern: &str) -> Result<(), PatternError> {
+    let name = hostname(pattern);
+    let (is_suffix, name) = match name.strip_prefix('.') {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+    let fixed = match name.rfind(['*', '?']) {
+        Some(index) => name[index..].split_once('.').map_or("", |(_, fixed)| fixed),
+        None if is_suffix => name,
+        None => return Ok(()),
+    };
+    if fixed.is_empty() || is_public_suffix(fixed) {
+        return Err(PatternError::PublicSuffix(pattern.to_string()));
+    }
+    Ok(())
+}
+
+/// Matcher which matches any host under a registrable domain
+///
+/// Host matches when its registrable domain is equal to configured domain
+/// ignoring ascii case and port.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::matcher::Matcher as _;
+/// use tower_allowed_hosts::psl::RegistrableDomain;
+///
+/// let matcher = RegistrableDomain::new("example.co.uk").unwrap();
+/// assert!(matcher.matches_value("api.example.co.uk:8080"));
+/// assert!(!matcher.matches_value("example.org.uk"));
+/// assert!(RegistrableDomain::new("co.uk").is_err());
+/// ```
+#[derive(Clone)]
+pub struct RegistrableDomain {
+    domain: String,
+}
+
+impl RegistrableDomain {
+    /// Create new registrable domain matcher
+    ///
+    /// # Errors
+    /// Returns [`PatternError::PublicSuffix`] if domain is a public suffix and
+    /// [`PatternError::NotRegistrableDomain`] if domain is not a registrable
+    /// domain
+    pub fn new(domain: &str) -> Result<Self, PatternError> {
+        let domain = domain
+            .strip_suffix('.')
+            .unwrap_or(domain)
+            .to_ascii_lowercase();
+        if is_public_suffix(&domain) {
+            return Err(PatternError::PublicSuffix(domain));
+        }
+        if registrable_domain(&domain) != Some(domain.as_str()) {
+            return Err(PatternError::NotRegistrableDomain(domain));
+        }
+        Ok(Self { domain })
+    }
+}
+
+impl Matcher for RegistrableDomain {
+    fn matches_value(&self, value: &str) -> bool {
+        registrable_domain(hostname(value))
+            .is_some_and(|domain| domain.eq_ignore_ascii_case(&self.domain))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matcher::Matcher as _;
+    use crate::psl::{
+        RegistrableDomain, check_pattern, is_public_suffix, public_suffix, registrable_domain,
+    };
+
+    #[test]
+    fn public_suffix_rules() {
+        assert_eq!(public_suffix("example.com"), Some("com"));
+        assert_eq!(public_suffix("EXAMPLE.CO.UK"), Some("CO.UK"));
+        assert_eq!(public_suffix("foo.bar.ck"), Some("bar.ck"));
+        assert_eq!(public_suffix("www.ck"), Some("ck"));
+        assert_eq!(public_suffix("example.unknowntld"), Some("unknowntld"));
+        assert_eq!(public_suffix("[::1]"), None);
+        assert_eq!(public_suffix(""), None);
+    }
+
+    #[test]
+    fn registrable_domain_rules() {
+        assert_eq!(registrable_domain("a.b.example.com"), Some("example.com"));
+        assert_eq!(registrable_domain("example.com."), Some("example.com"));
+        assert_eq!(registrable_domain("bar.ck"), None);
+        assert_eq!(registrable_domain("foo.bar.ck"), Some("foo.bar.ck"));
+        assert_eq!(registrable_domain("a.www.ck"), Some("www.ck"));
+        assert_eq!(registrable_domain("user.github.io"), Some("user.github.io"));
+        assert_eq!(registrable_domain("com"), None);
+        assert_eq!(registrable_domain(".com"), None);
+        assert_eq!(registrable_domain("a..com"), None);
+        assert!(is_public_suffix("github.io"));
+    }
+
+    #[test]
+    fn pattern_check() {
+        assert!(check_pattern("example.com").is_ok());
+        assert!(check_pattern("co.uk").is_ok());
+        assert!(check_pattern("*.example.com").is_ok());
+        assert!(check_pattern("api-*.example.com:8080").is_ok());
+        assert!(check_pattern(".example.com").is_ok());
+        assert!(check_pattern("*.co.uk").is_err());
+        assert!(check_pattern("*.*.co.uk").is_err());
+        assert!(check_pattern("*example.com").is_err());
+        assert!(check_pattern(".co.uk").is_err());
+        assert!(check_pattern("*").is_err());
+    }
+
+    #[test]
+    fn registrable_domain_matcher() {
+        let matcher = RegistrableDomain::new("Example.co.uk").unwrap();
+        assert!(matcher.matches_value("example.co.uk"));
+        assert!(matcher.matches_value("a.b.EXAMPLE.co.uk:443"));
+        assert!(!matcher.matches_value("example.com"));
+        assert!(!matcher.matches_value("co.uk"));
+        assert!(RegistrableDomain::new("co.uk").is_err());
+        assert!(RegistrableDomain::new("www.example.co.uk").is_err());
+    }
+}
@@ -1,18 +1,33 @@
-use std::collections::HashMap;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use http::header::{FORWARDED, HOST};
-use http::{HeaderMap, Request, Version};
+use http::header::{FORWARDED, HOST, HeaderName};
+use http::{HeaderMap, Method, Request, Version};
 use tower_layer::Layer;
 use tower_service::Service;
 
-use crate::Host;
-use crate::error::Error;
-use crate::matcher::{KeyValueMatcher, Matcher};
+use crate::error::{Error, InvalidForwardedKind, InvalidHostKind};
+use crate::forwarded::{self, ForwardedChain, Parameters};
+#[cfg(feature = "homograph")]
+use crate::homograph::HomographCheck;
+use crate::matcher::{Any, Decision, EachElement, ForwardedChainMatcher, KeyValueMatcher, Matcher};
+use crate::proxy::{
+    self, ForwardedPrecedence, HopSelection, TrustedProxies, UntrustedForwarded, XForwarded,
+};
+use crate::{Host, HostSource};
 
 type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
+/// `X-Forwarded-For` header name
+const X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+/// `X-Forwarded-Host` header name
+const X_FORWARDED_HOST: HeaderName = HeaderName::from_static("x-forwarded-host");
+/// `X-Forwarded-Port` header name
+const X_FORWARDED_PORT: HeaderName = HeaderName::from_static("x-forwarded-port");
+/// `X-Forwarded-Proto` header name
+const X_FORWARDED_PROTO: HeaderName = HeaderName::from_static("x-forwarded-proto");
+
 /// A layer that validates and allows incoming requests based on their host.
 ///
 /// This layer inspects the request authority/host and compares it against
@@ -30,12 +45,26 @@ type BoxError = Box<dyn std::error::Error + Send + Sync>;
 ///      - The `:authority` pseudo-header (via `req.uri().authority()`) is the
 ///        canonical source.
 ///      - If a `Host` header is present, it must match `:authority` or the
-///        request will be rejected.
+///        request will be rejected. Hosts are compared according to
+///        [`AuthorityComparison`].
 ///      - If `:authority` is missing, the request is rejected.
 ///    - For **HTTP/1.x and older**:
-///      - The `Host` header is used.
-///      - If the `Host` header is missing, the request is rejected (invalid per
-///        RFC 9112 §3.2).
+///      - If the `Host` header is missing or repeated, the request is rejected
+///        (invalid per RFC 9112 §3.2). HTTP/1.0 and HTTP/0.9 request may omit
+///        it.
+///      - If request target is in absolute-form, its authority is used instead
+///        of `Host` header (RFC 9112 §3.2.2). With [`AbsoluteForm::Strict`], a
+///        `Host` header which disagrees with it is rejected.
+///      - Otherwise the `Host` header is used, or [`DefaultHost`] if configured
+///        for HTTP/1.0 or HTTP/0.9 request without `Host` header.
+///
+/// ## CONNECT requests
+///
+/// Target of `CONNECT` request is address of tunnel instead of origin host.
+/// It is handled according to configured [`ConnectPolicy`]. HTTP/2 and HTTP/3
+/// extended `CONNECT` (RFC 8441, used for `WebSocket`) carries `:scheme` and
+/// `:path` and its `:authority` is origin host so it is always handled as
+/// normal request.
 ///
 /// ## Forwarded header usage
 ///
@@ -45,6 +74,9 @@ type BoxError = Box<dyn std::error::Error + Send + Sync>;
 /// client-facing host even if one or more proxies have rewritten the `Host`
 /// header or `:authority`.
 ///
+/// When [`TrustedProxies`] is configured, `Forwarded` header is only used
+/// if peer address of connection is part of trusted proxy networks.
+///
 /// For example:
 ///
 /// ```text
@@ -53,7 +85,17 @@ type BoxError = Box<dyn std::error::Error + Send + Sync>;
 /// ```
 ///
 /// With `forwarded_matcher = ("signature", "random_value")`, the extracted
-/// host will be `127.0.0.1`. Other entries are ignored.
+/// host will be `127.0.0.1`. Other entries are ignored. Element which is used
+/// is selected according to [`HopSelection`].
+///
+/// ## X-Forwarded headers usage
+///
+/// When [`XForwarded`] and [`TrustedProxies`] are configured, host of
+/// `X-Forwarded-Host` header sent by trusted proxy is also used. Without
+/// [`TrustedProxies`] these headers are ignored. When both
+/// header families provide host, one is used according to
+/// [`ForwardedPrecedence`] and disagreement can be detected with
+/// [`HostConflict`].
 ///
 /// ## ⚠️ Security warning
 ///
@@ -75,6 +117,230 @@ type BoxError = Box<dyn std::error::Error + Send + Sync>;
 pub struct AllowedHostLayer<H, F> {
     host_matcher: H,
     forwarded_matcher: F,
+    config: Config,
+}
+
+/// Validation applied to resolved host before it is matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HostValidation {
+    /// host is passed to matcher without any syntax validation
+    #[default]
+    Lenient,
+    /// host must follow RFC 3986 host grammar with numeric port in range,
+    /// registered name limited to unreserved characters, label at most 63
+    /// characters and host at most 253 characters
+    Strict,
+}
+
+/// Parsing mode of `Host` header value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HostParsing {
+    /// surrounding whitespace and double quotes are removed from `Host` header
+    /// so `"example.com"` is parsed as `example.com`
+    #[default]
+    Lenient,
+    /// `Host` header surrounded by whitespace or containing double quotes is
+    /// rejected as RFC 9110 doesn't allow them
+    Strict,
+}
+
+/// Handling of `Host` header for HTTP/1.x request with absolute-form target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AbsoluteForm {
+    /// authority of request target is used and `Host` header is not compared
+    /// with it
+    #[default]
+    Lenient,
+    /// authority of request target is used and `Host` header, if present, must
+    /// be same as authority
+    Strict,
+}
+
+/// Comparison of `Host` header with `:authority` or absolute-form target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AuthorityComparison {
+    /// hostname is compared case-insensitively and missing port is considered
+    /// same as default port of request scheme
+    #[default]
+    Lenient,
+    /// `Host` header must be byte for byte same as authority
+    Strict,
+}
+
+/// Host used for HTTP/1.0 request without `Host` header
+///
+/// HTTP/1.0 doesn't require `Host` header so old clients may omit it. Default
+/// host is validated by host matcher like any other host and [`HostSource`]
+/// extension of request is set to [`HostSource::Default`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultHost {
+    host: String,
+    http_09: bool,
+}
+
+impl DefaultHost {
+    /// Create new default host used for HTTP/1.0 request
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::DefaultHost;
+    ///
+    /// let default_host = DefaultHost::new("example.com");
+    /// ```
+    pub fn new<T>(host: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            host: host.into(),
+            http_09: false,
+        }
+    }
+
+    /// Also use default host for HTTP/0.9 request
+    #[must_use]
+    pub fn with_http_09(mut self) -> Self {
+        self.http_09 = true;
+        self
+    }
+
+    /// Get default host for request version if applicable
+    fn for_version(&self, version: Version) -> Option<&str> {
+        match version {
+            Version::HTTP_10 => Some(&self.host),
+            Version::HTTP_09 if self.http_09 => Some(&self.host),
+            _ => None,
+        }
+    }
+}
+
+/// Handling of `CONNECT` request whose target is address of tunnel
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub enum ConnectPolicy {
+    /// target is resolved as host of normal request and validated by host
+    /// matcher
+    #[default]
+    Normal,
+    /// `CONNECT` request is rejected
+    Reject,
+    /// target authority is validated by provided matcher instead of host
+    /// matcher, `Forwarded` header is not used
+    Matcher(Arc<dyn Matcher + Send + Sync>),
+}
+
+impl ConnectPolicy {
+    /// Create policy which validates target authority using provided matcher
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::ConnectPolicy;
+    ///
+    /// let policy = ConnectPolicy::matcher("proxy.example.com:443");
+    /// ```
+    pub fn matcher<M>(connect_matcher: M) -> Self
+    where
+        M: Matcher + Send + Sync + 'static,
+    {
+        connect_matcher.check_config();
+        Self::Matcher(Arc::new(connect_matcher))
+    }
+}
+
+/// Action taken when host sources of request conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConflictAction {
+    /// conflict is logged as warning with `tracing` feature and resolved host
+    /// is used
+    Warn,
+    /// request is rejected with [`Error::ConflictingHost`]
+    Reject,
+}
+
+/// Detection of conflicting host sources
+///
+/// Hosts of trusted `Forwarded` and `X-Forwarded-Host` headers are compared
+/// when both are present. Optionally host resolved from `Host` header or
+/// authority is also compared with forwarded host. Hosts are compared by
+/// hostname ignoring case, port and trailing dot.
+///
+/// # Example
+/// ```
+/// use tower_allowed_hosts::service::{ConflictAction, HostConflict};
+///
+/// let host_conflict = HostConflict::new(ConflictAction::Reject).with_request_host();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostConflict {
+    action: ConflictAction,
+    request_host: bool,
+}
+
+impl HostConflict {
+    /// Create new conflict detection with provided action
+    #[must_use]
+    pub fn new(action: ConflictAction) -> Self {
+        Self {
+            action,
+            request_host: false,
+        }
+    }
+
+    /// Also compare forwarded host with host resolved from `Host` header or
+    /// authority. Proxy which rewrites `Host` header to internal name will
+    /// conflict
+    #[must_use]
+    pub fn with_request_host(mut self) -> Self {
+        self.request_host = true;
+        self
+    }
+
+    /// Check hosts of sources and return error if conflict should reject
+    /// request
+    fn check(&self, hosts: Vec<(HostSource, String)>) -> Result<(), Error> {
+        let Some((_, resolved)) = hosts.first() else {
+            return Ok(());
+        };
+        let resolved = crate::host::hostname(resolved);
+        let conflicting = hosts
+            .iter()
+            .any(|(_, host)| !crate::host::hostname(host).eq_ignore_ascii_case(resolved));
+        if !conflicting {
+            return Ok(());
+        }
+        match self.action {
+            ConflictAction::Warn => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("{}", Error::ConflictingHost(hosts));
+                Ok(())
+            }
+            ConflictAction::Reject => Err(Error::ConflictingHost(hosts)),
+        }
+    }
+}
+
+/// Configuration of layer which doesn't depend upon matcher
+#[derive(Clone, Default)]
+struct Config {
+    connect_policy: ConnectPolicy,
+    default_host: Option<DefaultHost>,
+    forwarded_chain_matcher: Option<Arc<dyn ForwardedChainMatcher + Send + Sync>>,
+    forwarded_matcher: bool,
+    hop_selection: HopSelection,
+    host_conflict: Option<HostConflict>,
+    trusted_proxies: Option<TrustedProxies>,
+    x_forwarded: Option<XForwarded>,
+    absolute_form: AbsoluteForm,
+    authority_comparison: AuthorityComparison,
+    host_parsing: HostParsing,
+    host_validation: HostValidation,
+    #[cfg(feature = "homograph")]
+    homograph_check: Option<HomographCheck>,
 }
 
 impl<H> AllowedHostLayer<H, ()> {
@@ -84,14 +350,23 @@ impl<H> AllowedHostLayer<H, ()> {
     /// ```
     /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com");
     /// ```
-    pub fn new(host_matcher: H) -> Self {
+    pub fn new(host_matcher: H) -> Self
+    where
+        H: Matcher,
+    {
+        host_matcher.check_config();
         Self {
             host_matcher,
             forwarded_matcher: (),
+            config: Config::default(),
         }
     }
 }
 
+#[expect(
+    clippy::multiple_inherent_impl,
+    reason = "constructor and forwarded matcher are kept in separate blocks"
+)]
 impl<H> AllowedHostLayer<H, ()> {
     /// Extend a host matcher with provided forwarded matcher
     ///
@@ -108,10 +383,273 @@ impl<H> AllowedHostLayer<H, ()> {
         AllowedHostLayer {
             host_matcher: self.host_matcher,
             forwarded_matcher,
+            config: Config {
+                forwarded_matcher: true,
+                ..self.config
+            },
         }
     }
 }
 
+impl<H, F> AllowedHostLayer<H, F> {
+    /// Set comparison of `Host` header with `:authority` of HTTP/2 and HTTP/3
+    /// request or authority of HTTP/1.x absolute-form target
+    ///
+    /// With [`AuthorityComparison::Lenient`] `Example.com` and
+    /// `example.com:443` of `https` request are considered same as
+    /// `example.com`. Default is [`AuthorityComparison::Lenient`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::AuthorityComparison;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_authority_comparison(AuthorityComparison::Strict);
+    /// ```
+    #[must_use]
+    pub fn with_authority_comparison(mut self, authority_comparison: AuthorityComparison) -> Self {
+        self.config.authority_comparison = authority_comparison;
+        self
+    }
+
+    /// Set matcher which selects trusted element from whole `Forwarded` chain
+    ///
+    /// When set, chain matcher is used instead of forwarded matcher and hop
+    /// selection. [`EachElement`] can be used to create chain matcher from
+    /// per element [`KeyValueMatcher`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::matcher::EachElement;
+    /// use tower_allowed_hosts::proxy::HopSelection;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_forwarded_chain_matcher(
+    ///         EachElement::new(("by", "_proxy")).with_hop_selection(HopSelection::Rightmost),
+    ///     );
+    /// ```
+    #[must_use]
+    pub fn with_forwarded_chain_matcher<C>(mut self, forwarded_chain_matcher: C) -> Self
+    where
+        C: ForwardedChainMatcher + Send + Sync + 'static,
+    {
+        self.config.forwarded_chain_matcher = Some(Arc::new(forwarded_chain_matcher));
+        self
+    }
+
+    /// Set strategy used to select element of `Forwarded` header chain
+    ///
+    /// Default is [`HopSelection::FirstMatch`] which uses leftmost element
+    /// accepted by forwarded matcher. Leftmost element is the one most easily
+    /// controlled by client so prefer other strategies when proxies in front
+    /// of service are known.
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::matcher::Any;
+    /// use tower_allowed_hosts::proxy::HopSelection;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_forwarded_matcher(Any)
+    ///     .with_hop_selection(HopSelection::TrustedHops(2));
+    /// ```
+    #[must_use]
+    pub fn with_hop_selection(mut self, hop_selection: HopSelection) -> Self {
+        self.config.hop_selection = hop_selection;
+        self
+    }
+
+    /// Set detection of conflicting host sources
+    ///
+    /// Disagreement between `Forwarded` and `X-Forwarded-Host` headers or
+    /// between forwarded host and `Host` header usually means spoofing or
+    /// misconfigured proxy. By default conflict is not detected.
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::matcher::Any;
+    /// use tower_allowed_hosts::proxy::{TrustedProxies, XForwarded};
+    /// use tower_allowed_hosts::service::{ConflictAction, HostConflict};
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_forwarded_matcher(Any)
+    ///     .with_x_forwarded(XForwarded::new())
+    ///     .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse()?]))
+    ///     .with_host_conflict(HostConflict::new(ConflictAction::Reject));
+    /// # Ok::<(), tower_allowed_hosts::error::PatternError>(())
+    /// ```
+    #[must_use]
+    pub fn with_host_conflict(mut self, host_conflict: HostConflict) -> Self {
+        self.config.host_conflict = Some(host_conflict);
+        self
+    }
+
+    /// Set proxy networks whose `Forwarded` header is trusted
+    ///
+    /// `Forwarded` header is only used when peer address of connection is part
+    /// of trusted proxy networks and element is accepted by forwarded matcher.
+    /// Use [`Any`] as forwarded matcher to accept any element sent by trusted
+    /// proxy. Peer address is read from [`std::net::SocketAddr`] or axum
+    /// `ConnectInfo<SocketAddr>` extension.
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::matcher::Any;
+    /// use tower_allowed_hosts::proxy::TrustedProxies;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_forwarded_matcher(Any)
+    ///     .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse()?]));
+    /// # Ok::<(), tower_allowed_hosts::error::PatternError>(())
+    /// ```
+    #[must_use]
+    pub fn with_trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.config.trusted_proxies = Some(trusted_proxies);
+        self
+    }
+
+    /// Enable legacy `X-Forwarded-Host`, `X-Forwarded-Proto` and
+    /// `X-Forwarded-Port` headers as source of host
+    ///
+    /// **Without [`AllowedHostLayer::with_trusted_proxies`] this setting has
+    /// no effect.** Headers are only used when peer address of connection is
+    /// part of trusted proxy networks, otherwise they are ignored. With
+    /// `tracing` feature a warning is logged when layer is applied without
+    /// trusted proxies. Forwarded matcher and chain matcher are only applied
+    /// to `Forwarded` header.
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::proxy::{TrustedProxies, XForwarded};
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_x_forwarded(XForwarded::new())
+    ///     .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse()?]));
+    /// # Ok::<(), tower_allowed_hosts::error::PatternError>(())
+    /// ```
+    #[must_use]
+    pub fn with_x_forwarded(mut self, x_forwarded: XForwarded) -> Self {
+        self.config.x_forwarded = Some(x_forwarded);
+        self
+    }
+
+    /// Set host used for HTTP/1.0 request without `Host` header
+    ///
+    /// By default such request is rejected with [`Error::MissingHost`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::DefaultHost;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_default_host(DefaultHost::new("example.com").with_http_09());
+    /// ```
+    #[must_use]
+    pub fn with_default_host(mut self, default_host: DefaultHost) -> Self {
+        self.config.default_host = Some(default_host);
+        self
+    }
+
+    /// Set handling of `CONNECT` request
+    ///
+    /// Extended `CONNECT` of HTTP/2 and HTTP/3 is not affected by policy.
+    /// Default is [`ConnectPolicy::Normal`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::ConnectPolicy;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_connect_policy(ConnectPolicy::Reject);
+    /// ```
+    #[must_use]
+    pub fn with_connect_policy(mut self, connect_policy: ConnectPolicy) -> Self {
+        self.config.connect_policy = connect_policy;
+        self
+    }
+
+    /// Set handling of `Host` header for HTTP/1.x request with absolute-form
+    /// target such as `GET http://example.com/ HTTP/1.1`
+    ///
+    /// Authority of absolute-form target is always used as host but HTTP/1.1
+    /// request must still contain single `Host` header. With
+    /// [`AbsoluteForm::Strict`] request is rejected with
+    /// [`Error::MismatchAuthorityHost`] when `Host` header disagrees with it.
+    /// Default is [`AbsoluteForm::Lenient`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::AbsoluteForm;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_absolute_form(AbsoluteForm::Strict);
+    /// ```
+    #[must_use]
+    pub fn with_absolute_form(mut self, absolute_form: AbsoluteForm) -> Self {
+        self.config.absolute_form = absolute_form;
+        self
+    }
+
+    /// Set parsing mode of `Host` header value
+    ///
+    /// With [`HostParsing::Strict`] quoted or padded `Host` header is rejected
+    /// with [`Error::InvalidHost`] instead of being trimmed. With
+    /// [`HostParsing::Lenient`] invalid `Host` header of HTTP/2 and HTTP/3
+    /// request is ignored as `:authority` is used. Default is
+    /// [`HostParsing::Lenient`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::HostParsing;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_host_parsing(HostParsing::Strict);
+    /// ```
+    #[must_use]
+    pub fn with_host_parsing(mut self, host_parsing: HostParsing) -> Self {
+        self.config.host_parsing = host_parsing;
+        self
+    }
+
+    /// Set validation applied to resolved host
+    ///
+    /// With [`HostValidation::Strict`] invalid host is rejected with
+    /// [`Error::InvalidHost`] containing reason of failure. Default is
+    /// [`HostValidation::Lenient`].
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::service::HostValidation;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_host_validation(HostValidation::Strict);
+    /// ```
+    #[must_use]
+    pub fn with_host_validation(mut self, host_validation: HostValidation) -> Self {
+        self.config.host_validation = host_validation;
+        self
+    }
+
+    /// Reject internationalized host which can be confused with other host
+    ///
+    /// Host is checked before host matcher and rejected with
+    /// [`Error::ConfusableHost`] if it fails provided homograph check
+    ///
+    /// # Example
+    /// ```
+    /// use tower_allowed_hosts::homograph::HomographCheck;
+    ///
+    /// let layer = tower_allowed_hosts::AllowedHostLayer::new("example.com")
+    ///     .with_homograph_check(HomographCheck::new().with_protected_hosts(["example.com"]));
+    /// ```
+    #[cfg(feature = "homograph")]
+    #[must_use]
+    pub fn with_homograph_check(mut self, homograph_check: HomographCheck) -> Self {
+        self.config.homograph_check = Some(homograph_check);
+        self
+    }
+}
+
 impl<H, F, S> Layer<S> for AllowedHostLayer<H, F>
 where
     H: Clone,
@@ -120,6 +658,10 @@ where
     type Service = AllowedHost<H, F, S>;
 
     fn layer(&self, inner: S) -> Self::Service {
+        #[cfg(feature = "tracing")]
+        if self.config.x_forwarded.is_some() && self.config.trusted_proxies.is_none() {
+            tracing::warn!("X-Forwarded headers are ignored as trusted proxies are not configured");
+        }
         Self::Service {
             inner,
             layer: self.clone(),
@@ -151,18 +693,18 @@ where
     }
 
     fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
-        match get_host(&req, &self.layer.forwarded_matcher) {
-            Ok(host_val) => {
-                let host_allowed = self.layer.host_matcher.matches_value(host_val.as_str());
-
-                if host_allowed {
+        match decide_host(&req, &self.layer) {
+            Ok((host_val, source, decision)) => {
+                if decision.allowed {
                     req.extensions_mut().insert(Host(host_val.clone()));
+                    req.extensions_mut().insert(source);
                 }
 
                 Self::Future {
                     response_future: self.inner.call(req),
                     host: Ok(host_val),
-                    host_allowed,
+                    host_allowed: decision.allowed,
+                    rule: decision.rule,
                 }
             }
             Err(err) => {
@@ -170,6 +712,7 @@ where
                     response_future: self.inner.call(req),
                     host: Err(err),
                     host_allowed: false,
+                    rule: None,
                 }
             }
         }
@@ -183,6 +726,7 @@ pub struct AllowedHostFuture<F> {
     response_future: F,
     host: Result<String, Error>,
     host_allowed: bool,
+    rule: Option<Arc<str>>,
 }
 
 impl<F, Response, E> Future for AllowedHostFuture<F>
@@ -207,6 +751,15 @@ where
                 }
             }
             (Ok(blocked_host), false) => {
+                if let Some(rule) = &this.rule {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!("blocked host: {} by {}", blocked_host, rule);
+                    return Poll::Ready(Err(Error::HostDenied {
+                        host: blocked_host.clone(),
+                        rule: rule.to_string(),
+                    }
+                    .into()));
+                }
                 #[cfg(feature = "tracing")]
                 tracing::debug!("blocked host: {}", blocked_host);
                 Poll::Ready(Err(Error::HostNotAllowed(blocked_host.clone()).into()))
@@ -216,105 +769,366 @@ where
     }
 }
 
-/// Extract the host from the request headers based on the layer configuration.
-fn get_host<F, ReqBody>(req: &Request<ReqBody>, forwarded_matcher: &F) -> Result<String, Error>
+/// Resolve host from request and decide whether it is allowed according to
+/// layer configuration
+fn decide_host<H, F, ReqBody>(
+    req: &Request<ReqBody>,
+    layer: &AllowedHostLayer<H, F>,
+) -> Result<(String, HostSource, Decision), Error>
 where
+    H: Matcher,
     F: KeyValueMatcher,
 {
-    let headers = req.headers();
+    if is_connect_tunnel(req) {
+        match &layer.config.connect_policy {
+            ConnectPolicy::Normal => {}
+            ConnectPolicy::Reject => return Err(Error::ConnectNotAllowed),
+            ConnectPolicy::Matcher(connect_matcher) => {
+                let target = req
+                    .uri()
+                    .authority()
+                    .ok_or(Error::MissingAuthority)?
+                    .to_string();
+                validate_host(&target, &layer.config)?;
+                let decision = connect_matcher.decide(&target);
+                return Ok((target, HostSource::Authority, decision));
+            }
+        }
+    }
+    let (host, source) = get_host(req, &layer.forwarded_matcher, &layer.config)?;
+    validate_host(&host, &layer.config)?;
+    let decision = layer.host_matcher.decide(&host);
+    Ok((host, source, decision))
+}
+
+/// Check whether request is `CONNECT` request for tunnel. Extended `CONNECT`
+/// of HTTP/2 and HTTP/3 contains scheme and targets origin host instead
+fn is_connect_tunnel<ReqBody>(req: &Request<ReqBody>) -> bool {
+    if req.method() != Method::CONNECT {
+        return false;
+    }
+    let extended =
+        matches!(req.version(), Version::HTTP_2 | Version::HTTP_3) && req.uri().scheme().is_some();
+    !extended
+}
 
-    if let Some(forwarded_host) = extract_from_forwarded(headers, forwarded_matcher)? {
-        return Ok(forwarded_host);
+/// Validate resolved host according to layer configuration
+fn validate_host(host: &str, config: &Config) -> Result<(), Error> {
+    if config.host_validation == HostValidation::Strict {
+        crate::host::validate(host).map_err(Error::InvalidHost)?;
     }
+    #[cfg(feature = "homograph")]
+    if let Some(homograph_check) = &config.homograph_check {
+        homograph_check.check(host)?;
+    }
+    Ok(())
+}
 
+/// Extract the host from the request headers based on the layer configuration.
+fn get_host<F, ReqBody>(
+    req: &Request<ReqBody>,
+    forwarded_matcher: &F,
+    config: &Config,
+) -> Result<(String, HostSource), Error>
+where
+    F: KeyValueMatcher,
+{
+    if forwarded_trusted(req, config)? {
+        let mut hosts = Vec::new();
+        if let Some(forwarded_host) = extract_from_forwarded(req, forwarded_matcher, config)? {
+            hosts.push((HostSource::Forwarded, forwarded_host));
+        }
+        if let Some(x_forwarded_host) = extract_from_x_forwarded(req, config)? {
+            hosts.push((HostSource::XForwarded, x_forwarded_host));
+        }
+        if config
+            .x_forwarded
+            .as_ref()
+            .is_some_and(|x_forwarded| x_forwarded.precedence() == ForwardedPrecedence::XForwarded)
+        {
+            hosts.reverse();
+        }
+        if let Some((source, host)) = hosts.first().cloned() {
+            if let Some(host_conflict) = &config.host_conflict {
+                if host_conflict.request_host
+                    && let Ok((request_host, request_source)) = get_request_host(req, config)
+                {
+                    hosts.push((request_source, request_host));
+                }
+                host_conflict.check(hosts)?;
+            }
+            return Ok((host, source));
+        }
+    }
+
+    get_request_host(req, config)
+}
+
+/// Resolve host from `Host` header or authority according to HTTP version
+fn get_request_host<ReqBody>(
+    req: &Request<ReqBody>,
+    config: &Config,
+) -> Result<(String, HostSource), Error> {
+    let headers = req.headers();
     match req.version() {
         // HTTP/2 and HTTP/3 use the :authority pseudo-header
         Version::HTTP_2 | Version::HTTP_3 => {
             if let Some(authority) = req.uri().authority() {
                 // :authority must be used, Host (if present) must match.
-                if let Ok(host) = extract_from_host(headers)
-                    && host != authority.as_str()
+                // Invalid Host is only rejected with strict parsing
+                let host = match config.host_parsing {
+                    HostParsing::Lenient => {
+                        extract_from_host(headers, config.host_parsing)
+                            .ok()
+                            .flatten()
+                    }
+                    HostParsing::Strict => extract_from_host(headers, config.host_parsing)?,
+                };
+                if let Some(host) = host
+                    && !same_authority(req, &host, authority.as_str(), config)
                 {
                     return Err(Error::MismatchAuthorityHost);
                 }
-                return Ok(authority.to_string());
+                return Ok((authority.to_string(), HostSource::Authority));
             }
             Err(Error::MissingAuthority)
         }
-        // HTTP/1.1 and earlier: must use the Host header
+        // HTTP/1.1 and earlier: absolute-form target wins over Host header,
+        // otherwise Host header must be used
         Version::HTTP_11 | Version::HTTP_10 | Version::HTTP_09 => {
-            if let Ok(host) = extract_from_host(headers) {
-                return Ok(host);
+            // HTTP/1.1 request without Host header is invalid even when target
+            // is in absolute-form (RFC 9112 §3.2)
+            let host = extract_from_host(headers, config.host_parsing)?;
+            if host.is_none() && req.version() == Version::HTTP_11 {
+                return Err(Error::MissingHost);
+            }
+            if let Some(authority) = req.uri().authority() {
+                // userinfo is not part of host (RFC 9112 §3.2.2)
+                let authority = authority
+                    .as_str()
+                    .rsplit_once('@')
+                    .map_or(authority.as_str(), |(_, host_port)| host_port);
+                if config.absolute_form == AbsoluteForm::Strict
+                    && let Some(host) = &host
+                    && !same_authority(req, host, authority, config)
+                {
+                    return Err(Error::MismatchAuthorityHost);
+                }
+                return Ok((authority.to_string(), HostSource::Authority));
             }
-            Err(Error::MissingHost)
+            if let Some(host) = host {
+                return Ok((host, HostSource::Host));
+            }
+            // HTTP/1.0 and HTTP/0.9 may omit Host header
+            config
+                .default_host
+                .as_ref()
+                .and_then(|default_host| default_host.for_version(req.version()))
+                .map(|default_host| (default_host.to_string(), HostSource::Default))
+                .ok_or(Error::MissingHost)
         }
         // Future-proof fallback
         _ => Err(Error::UnsupportedHttpVersion),
     }
 }
 
-/// Extract host from `Host` headers.
-fn extract_from_host(headers: &HeaderMap) -> Result<String, Error> {
+/// Check whether forwarded headers of request can be trusted based on peer
+/// address of connection
+fn forwarded_trusted<ReqBody>(req: &Request<ReqBody>, config: &Config) -> Result<bool, Error> {
+    let Some(trusted_proxies) = &config.trusted_proxies else {
+        return Ok(true);
+    };
+    if proxy::peer_addr(req.extensions()).is_some_and(|peer| trusted_proxies.contains(peer)) {
+        return Ok(true);
+    }
+    let forwarded_present = req.headers().contains_key(FORWARDED)
+        || (config.x_forwarded.is_some() && req.headers().contains_key(X_FORWARDED_HOST));
+    if trusted_proxies.untrusted() == UntrustedForwarded::Reject && forwarded_present {
+        return Err(Error::UntrustedForwarded);
+    }
+    Ok(false)
+}
+
+/// Compare `Host` header with authority according to layer configuration
+fn same_authority<ReqBody>(
+    req: &Request<ReqBody>,
+    host: &str,
+    authority: &str,
+    config: &Config,
+) -> bool {
+    match config.authority_comparison {
+        AuthorityComparison::Lenient => {
+            let default_port = match req.uri().scheme_str() {
+                Some("https" | "wss") => Some("443"),
+                Some("http" | "ws") => Some("80"),
+                _ => None,
+            };
+            crate::host::equivalent(host, authority, default_port)
+        }
+        AuthorityComparison::Strict => host == authority,
+    }
+}
+
+/// Extract host from `Host` headers. Returns `None` if `Host` header is missing
+fn extract_from_host(headers: &HeaderMap, parsing: HostParsing) -> Result<Option<String>, Error> {
     let mut host_headers = headers.get_all(HOST).iter();
-    let first_host = host_headers.next().ok_or(Error::MissingHost)?;
+    let Some(first_host) = host_headers.next() else {
+        return Ok(None);
+    };
     if host_headers.next().is_some() {
         return Err(Error::MultipleHostHeader);
     }
     let host_str = first_host
         .to_str()
-        .map_err(|_| Error::InvalidHost)?
-        .trim()
-        .trim_matches('"')
-        .to_string();
-    Ok(host_str)
+        .map_err(|_| Error::InvalidHost(InvalidHostKind::NonVisibleAscii))?;
+    let host_str = match parsing {
+        HostParsing::Lenient => host_str.trim().trim_matches('"'),
+        HostParsing::Strict => {
+            if host_str.trim() != host_str {
+                return Err(Error::InvalidHost(InvalidHostKind::Padded));
+            }
+            if host_str.contains('"') {
+                return Err(Error::InvalidHost(InvalidHostKind::Quoted));
+            }
+            host_str
+        }
+    };
+    Ok(Some(host_str.to_string()))
 }
 
-/// Extract host from `Forwarded` headers only extract host header from allowed
-/// forwarded by values else return None
-fn extract_from_forwarded<F>(
-    headers: &HeaderMap,
+/// Extract host from `Forwarded` headers. Element is selected by forwarded
+/// chain matcher if configured else by forwarded matcher according to hop
+/// selection. Header is only parsed when either matcher is configured. Return
+/// None if no element is selected
+fn extract_from_forwarded<F, ReqBody>(
+    req: &Request<ReqBody>,
     forwarded_matcher: &F,
+    config: &Config,
 ) -> Result<Option<String>, Error>
 where
     F: KeyValueMatcher,
 {
-    for forwarded_header in headers.get_all(FORWARDED) {
-        let header_str = String::from_utf8(forwarded_header.as_bytes().to_vec())
-            .map_err(|_| Error::InvalidForwardedHeader)?;
-        for header_entry in header_str.split(',') {
-            let (host_value, token_present) = parse_forwarded_entry(header_entry)?;
-
-            if let Some(host) = host_value
-                && forwarded_matcher.matches_key_value(&token_present)
-            {
-                return Ok(Some(host));
-            }
-        }
+    // without any matcher no element can be selected so header is not parsed
+    // and malformed value doesn't affect request
+    if !config.forwarded_matcher && config.forwarded_chain_matcher.is_none() {
+        return Ok(None);
+    }
+    let mut elements = Vec::new();
+    for forwarded_header in req.headers().get_all(FORWARDED) {
+        let header_str = std::str::from_utf8(forwarded_header.as_bytes())
+            .map_err(|_| Error::InvalidForwardedHeader(InvalidForwardedKind::InvalidEncoding))?;
+        elements.extend(forwarded::parse_pairs(header_str).map_err(Error::InvalidForwardedHeader)?);
     }
-    Ok(None)
-}
 
-/// Parse a single Forwarded header entry and extract host + token presence
-fn parse_forwarded_entry(entry: &str) -> Result<(Option<String>, HashMap<String, String>), Error> {
-    let mut host_value = None;
-    let mut token_map = HashMap::new();
+    let mut chain = ForwardedChain::new(elements.iter().map(|pairs| Parameters::new(pairs)));
+    if let Some(peer_addr) = proxy::peer_addr(req.extensions()) {
+        chain = chain.with_peer_addr(peer_addr);
+    }
+    if let Some(trusted_proxies) = &config.trusted_proxies {
+        chain = chain.with_trusted_proxies(trusted_proxies);
+    }
 
-    for part in entry.split(';') {
-        let part = part.trim();
-        if part.is_empty() {
-            continue;
+    let selected = match &config.forwarded_chain_matcher {
+        Some(chain_matcher) => chain_matcher.select(&chain),
+        None => {
+            EachElement::new(forwarded_matcher)
+                .with_hop_selection(config.hop_selection)
+                .select(&chain)
         }
+    };
+    Ok(selected
+        .and_then(|parameters| parameters.get("host"))
+        .map(ToString::to_string))
+}
 
-        let (key, value) = part.split_once('=').ok_or(Error::InvalidForwardedHeader)?;
+/// Extract host from `X-Forwarded-*` headers if enabled along with trusted
+/// proxies. Lists of values are aligned from right and hop is selected
+/// according to hop selection. Return None if no hop is selected
+fn extract_from_x_forwarded<ReqBody>(
+    req: &Request<ReqBody>,
+    config: &Config,
+) -> Result<Option<String>, Error> {
+    // headers have no parameter which can be verified so they are only used
+    // when sent by trusted proxy
+    let (Some(x_forwarded), Some(_)) = (&config.x_forwarded, &config.trusted_proxies) else {
+        return Ok(None);
+    };
+    let headers = req.headers();
+    let hosts = x_forwarded_values(headers, &X_FORWARDED_HOST)?;
+    if hosts.is_empty() {
+        return Ok(None);
+    }
+    let protos = x_forwarded_values(headers, &X_FORWARDED_PROTO)?;
+    let ports = x_forwarded_values(headers, &X_FORWARDED_PORT)?;
+    let fors = x_forwarded_values(headers, &X_FORWARDED_FOR)?;
+
+    // proxies append to every header so value of same hop is at same distance
+    // from right
+    let aligned = |values: &[&str], index: usize| -> Option<String> {
+        (values.len() + index)
+            .checked_sub(hosts.len())
+            .and_then(|index| values.get(index))
+            .filter(|value| !value.is_empty())
+            .map(ToString::to_string)
+    };
+    let elements = (0..hosts.len())
+        .map(|index| {
+            [
+                ("for", aligned(&fors, index)),
+                ("host", aligned(&hosts, index)),
+                ("proto", aligned(&protos, index)),
+                ("port", aligned(&ports, index)),
+            ]
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name.to_string(), value)))
+            .collect::<forwarded::Pairs>()
+        })
+        .collect::<Vec<_>>();
 
-        let key = key.trim().to_lowercase();
-        let value = value.trim().trim_matches('"').to_string();
+    let mut chain = ForwardedChain::new(elements.iter().map(|pairs| Parameters::new(pairs)));
+    if let Some(peer_addr) = proxy::peer_addr(req.extensions()) {
+        chain = chain.with_peer_addr(peer_addr);
+    }
+    if let Some(trusted_proxies) = &config.trusted_proxies {
+        chain = chain.with_trusted_proxies(trusted_proxies);
+    }
 
-        if key.as_str() == "host" {
-            host_value = Some(value.clone());
+    let Some(selected) = EachElement::new(Any)
+        .with_hop_selection(x_forwarded.hop_selection())
+        .select(&chain)
+    else {
+        return Ok(None);
+    };
+    let Some(host) = selected.get("host") else {
+        return Ok(None);
+    };
+    let default_port = match selected.get("proto") {
+        Some(proto) if proto.eq_ignore_ascii_case("https") => Some("443"),
+        Some(proto) if proto.eq_ignore_ascii_case("http") => Some("80"),
+        _ => None,
+    };
+    if let Some(port) = selected.get("port") {
+        crate::host::validate_port(port).map_err(Error::InvalidHost)?;
+    }
+    match selected.get("port") {
+        Some(port) if crate::host::split_port(host).1.is_none() && Some(port) != default_port => {
+            Ok(Some(format!("{host}:{port}")))
         }
-        token_map.insert(key, value);
+        _ => Ok(Some(host.to_string())),
     }
+}
 
-    Ok((host_value, token_map))
+/// Collect comma separated values of all headers with provided name
+fn x_forwarded_values<'h>(
+    headers: &'h HeaderMap,
+    name: &HeaderName,
+) -> Result<Vec<&'h str>, Error> {
+    let mut values = Vec::new();
+    for header in headers.get_all(name) {
+        let header_str = header
+            .to_str()
+            .map_err(|_| Error::InvalidXForwardedHeader(name.to_string()))?;
+        values.extend(header_str.split(',').map(str::trim));
+    }
+    Ok(values)
 }
@@ -1,12 +1,25 @@
 use std::convert::Infallible;
+use std::sync::Arc;
 
 use bytes::Bytes;
-use http::{Request, Response};
+use http::{Method, Request, Response, Version};
 use http_body_util::BodyExt as _;
 use tower::{BoxError, Layer as _, ServiceExt as _, service_fn};
 
-use crate::AllowedHostLayer;
-use crate::matcher::Any;
+use crate::error::{InvalidForwardedKind, InvalidHostKind};
+use crate::forwarded::{ForwardedChain, Parameters};
+use crate::matcher::{
+    Any, AnyOf, ForwardedChainMatcher, HostGlob, HostTrie, Matcher, MatcherExt as _, RuleSet,
+    from_fn,
+};
+use crate::proxy::{
+    ForwardedPrecedence, HopSelection, TrustedProxies, UntrustedForwarded, XForwarded,
+};
+use crate::service::{
+    AbsoluteForm, AuthorityComparison, ConflictAction, ConnectPolicy, DefaultHost, HostConflict,
+    HostParsing, HostValidation,
+};
+use crate::{AllowedHostLayer, Error, Host, HostSource};
 
 type BoxBody = http_body_util::combinators::UnsyncBoxBody<Bytes, BoxError>;
 
@@ -226,3 +239,1471 @@ async fn any() {
         .await;
     assert!(any_value_host_header_res.is_ok());
 }
+
+#[tokio::test]
+async fn host_trie() {
+    let allowed_host_layer = AllowedHostLayer::new(
+        HostTrie::from_rules(["example.com", "*.tenant.example.com"]).unwrap(),
+    );
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "foo.tenant.example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let nested_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "foo.bar.tenant.example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(nested_host_header_res.is_err());
+}
+
+#[cfg(feature = "regex")]
+#[tokio::test]
+async fn full_regex() {
+    let allowed_host_layer =
+        AllowedHostLayer::new(crate::matcher::FullRegex::new("example.com").unwrap());
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let suffixed_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com.evil.net")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(suffixed_host_header_res.is_err());
+}
+
+#[tokio::test]
+async fn host_glob() {
+    let allowed_host_layer = AllowedHostLayer::new(HostGlob::new("127.0.0.?").unwrap());
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let empty_res = svc.clone().oneshot(Request::new(empty_body())).await;
+    assert!(empty_res.is_err());
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "127.0.0.1")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let another_ok = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "127.0.0.2")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(another_ok.is_ok());
+
+    let multiple_issue = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "127.0.0.20")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(multiple_issue.is_err());
+
+    let empty_label_issue = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "127.0.0..")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(empty_label_issue.is_err());
+}
+
+#[tokio::test]
+async fn host_array() {
+    let allowed_host_layer = AllowedHostLayer::new(["a.com", "b.com"]);
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "b.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let invalid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "c.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(invalid_host_header_res.is_err());
+}
+
+#[tokio::test]
+async fn closure() {
+    let allowed_host_layer =
+        AllowedHostLayer::new(
+            Arc::new(from_fn(|host: &str| host.ends_with(".example.com")))
+                as Arc<dyn Matcher + Send + Sync>,
+        );
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "api.example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let invalid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.org")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(invalid_host_header_res.is_err());
+}
+
+#[tokio::test]
+async fn rule_set() {
+    let allowed_host_layer = AllowedHostLayer::new(
+        RuleSet::new()
+            .deny("internal.example.com")
+            .allow(HostGlob::new("*.example.com").unwrap()),
+    );
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "api.example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let denied_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "internal.example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    let err = denied_host_header_res.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::HostDenied { rule, .. }) if rule == "deny rule #0"
+    ));
+
+    let unmatched_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.org")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    let err = unmatched_host_header_res.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(_))
+    ));
+}
+
+#[tokio::test]
+async fn nested_rule_set() {
+    let rule_set = RuleSet::new()
+        .deny("internal.example.com")
+        .allow(HostGlob::new("*.example.com").unwrap());
+    let svc = AllowedHostLayer::new(AnyOf::new(vec![rule_set]).or("example.org"))
+        .layer(service_fn(inner_svc));
+
+    for (host, rule) in [
+        ("internal.example.com", Some("deny rule #0")),
+        ("api.example.com", None),
+        ("example.org", None),
+        ("example.net", None),
+    ] {
+        let res = svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        match (host, rule) {
+            (_, Some(expected)) => {
+                assert!(matches!(
+                    res.unwrap_err().downcast_ref::<Error>(),
+                    Some(Error::HostDenied { rule, .. }) if rule == expected
+                ));
+            }
+            ("example.net", None) => {
+                assert!(matches!(
+                    res.unwrap_err().downcast_ref::<Error>(),
+                    Some(Error::HostNotAllowed(_))
+                ));
+            }
+            _ => assert!(res.is_ok(), "{host}"),
+        }
+    }
+}
+
+#[cfg(feature = "homograph")]
+#[tokio::test]
+async fn homograph() {
+    let allowed_host_layer = AllowedHostLayer::new(Any).with_homograph_check(
+        crate::homograph::HomographCheck::new().with_protected_hosts(["example.com"]),
+    );
+    let svc = allowed_host_layer.layer(service_fn(inner_svc));
+
+    let valid_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "xn--mnchen-3ya.de")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    let mixed_script_host_header_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "xn--exmple-4nf.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    let err = mixed_script_host_header_res.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::ConfusableHost(_))
+    ));
+}
+
+#[tokio::test]
+async fn host_validation() {
+    let lenient_svc = AllowedHostLayer::new(Any).layer(service_fn(inner_svc));
+    let strict_svc = AllowedHostLayer::new(Any)
+        .with_host_validation(HostValidation::Strict)
+        .layer(service_fn(inner_svc));
+
+    let valid_host_header_res = strict_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com:8080")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(valid_host_header_res.is_ok());
+
+    for (host, kind) in [
+        ("evil.com/foo", InvalidHostKind::InvalidCharacter),
+        ("user@host", InvalidHostKind::InvalidCharacter),
+        ("host:80:80", InvalidHostKind::InvalidCharacter),
+        ("%2e", InvalidHostKind::PercentEncoded),
+        ("host:99999", InvalidHostKind::InvalidPort),
+    ] {
+        let lenient_res = lenient_svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        assert!(lenient_res.is_ok());
+
+        let strict_res = strict_svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        let err = strict_res.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidHost(invalid_kind)) if *invalid_kind == kind
+        ));
+    }
+}
+
+#[tokio::test]
+async fn host_parsing() {
+    let lenient_svc = AllowedHostLayer::new("example.com").layer(service_fn(inner_svc));
+    let strict_svc = AllowedHostLayer::new("example.com")
+        .with_host_parsing(HostParsing::Strict)
+        .layer(service_fn(inner_svc));
+
+    for (host, kind) in [
+        ("\"example.com\"", InvalidHostKind::Quoted),
+        (" example.com", InvalidHostKind::Padded),
+        ("example.com\t", InvalidHostKind::Padded),
+    ] {
+        let lenient_res = lenient_svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        assert!(lenient_res.is_ok());
+
+        let strict_res = strict_svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        let err = strict_res.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidHost(invalid_kind)) if *invalid_kind == kind
+        ));
+    }
+
+    let strict_valid_res = strict_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(strict_valid_res.is_ok());
+
+    let strict_http2_res = strict_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .version(Version::HTTP_2)
+                .uri("https://example.com/")
+                .header("HOST", "\"example.com\"")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(strict_http2_res.is_err());
+
+    let repeated_host_request = || {
+        Request::builder()
+            .version(Version::HTTP_2)
+            .uri("https://example.com/")
+            .header("HOST", "example.com")
+            .header("HOST", "example.com")
+            .body(empty_body())
+            .unwrap()
+    };
+    let lenient_http2_res = lenient_svc.oneshot(repeated_host_request()).await;
+    assert!(lenient_http2_res.is_ok());
+    let strict_repeated_res = strict_svc.oneshot(repeated_host_request()).await;
+    assert!(matches!(
+        strict_repeated_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MultipleHostHeader)
+    ));
+}
+
+#[tokio::test]
+async fn absolute_form() {
+    let lenient_svc = AllowedHostLayer::new("example.com").layer(service_fn(inner_svc));
+    let strict_svc = AllowedHostLayer::new("example.com")
+        .with_absolute_form(AbsoluteForm::Strict)
+        .layer(service_fn(inner_svc));
+
+    let authority_res = lenient_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("http://example.com/")
+                .header("HOST", "127.0.0.1")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(authority_res.is_ok());
+
+    let authority_not_allowed_res = lenient_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("http://127.0.0.1/")
+                .header("HOST", "example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(authority_not_allowed_res.is_err());
+
+    let userinfo_res = lenient_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("http://user@example.com/")
+                .header("HOST", "example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(userinfo_res.is_ok());
+
+    let strict_matching_res = strict_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("http://example.com/")
+                .header("HOST", "example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(strict_matching_res.is_ok());
+
+    let strict_mismatch_res = strict_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("http://example.com/")
+                .header("HOST", "127.0.0.1")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        strict_mismatch_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MismatchAuthorityHost)
+    ));
+
+    let origin_form_res = strict_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/")
+                .header("HOST", "example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(origin_form_res.is_ok());
+}
+
+#[tokio::test]
+async fn absolute_form_invalid_host() {
+    let lenient_svc = AllowedHostLayer::new("example.com").layer(service_fn(inner_svc));
+    let strict_svc = AllowedHostLayer::new("example.com")
+        .with_absolute_form(AbsoluteForm::Strict)
+        .layer(service_fn(inner_svc));
+    let repeated_host_request = || {
+        Request::builder()
+            .uri("http://example.com/")
+            .header("HOST", "example.com")
+            .header("HOST", "127.0.0.1")
+            .body(empty_body())
+            .unwrap()
+    };
+
+    let lenient_res = lenient_svc.clone().oneshot(repeated_host_request()).await;
+    assert!(matches!(
+        lenient_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MultipleHostHeader)
+    ));
+
+    let missing_host_request = |version| {
+        Request::builder()
+            .version(version)
+            .uri("http://example.com/")
+            .body(empty_body())
+            .unwrap()
+    };
+    let missing_host_res = lenient_svc
+        .clone()
+        .oneshot(missing_host_request(Version::HTTP_11))
+        .await;
+    assert!(matches!(
+        missing_host_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MissingHost)
+    ));
+    let http10_res = lenient_svc
+        .oneshot(missing_host_request(Version::HTTP_10))
+        .await;
+    assert!(http10_res.is_ok());
+
+    let strict_res = strict_svc.oneshot(repeated_host_request()).await;
+    assert!(matches!(
+        strict_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MultipleHostHeader)
+    ));
+}
+
+fn connect_request(version: Version, uri: &str) -> Request<BoxBody> {
+    let mut builder = Request::builder()
+        .method(Method::CONNECT)
+        .version(version)
+        .uri(uri);
+    if matches!(version, Version::HTTP_11 | Version::HTTP_10) {
+        builder = builder.header("HOST", "example.com");
+    }
+    builder.body(empty_body()).unwrap()
+}
+
+#[tokio::test]
+async fn connect_policy() {
+    let normal_svc = AllowedHostLayer::new("example.com:443").layer(service_fn(inner_svc));
+    let reject_svc = AllowedHostLayer::new("example.com:443")
+        .with_connect_policy(ConnectPolicy::Reject)
+        .layer(service_fn(inner_svc));
+    let matcher_svc = AllowedHostLayer::new("example.com:443")
+        .with_connect_policy(ConnectPolicy::matcher("proxy.example.com:443"))
+        .layer(service_fn(inner_svc));
+
+    for version in [
+        Version::HTTP_10,
+        Version::HTTP_11,
+        Version::HTTP_2,
+        Version::HTTP_3,
+    ] {
+        let normal_res = normal_svc
+            .clone()
+            .oneshot(connect_request(version, "example.com:443"))
+            .await;
+        assert!(normal_res.is_ok());
+
+        let reject_res = reject_svc
+            .clone()
+            .oneshot(connect_request(version, "example.com:443"))
+            .await;
+        assert!(matches!(
+            reject_res.unwrap_err().downcast_ref::<Error>(),
+            Some(Error::ConnectNotAllowed)
+        ));
+
+        let matcher_allowed_res = matcher_svc
+            .clone()
+            .oneshot(connect_request(version, "proxy.example.com:443"))
+            .await;
+        assert!(matcher_allowed_res.is_ok());
+
+        let matcher_blocked_res = matcher_svc
+            .clone()
+            .oneshot(connect_request(version, "example.com:443"))
+            .await;
+        assert!(matches!(
+            matcher_blocked_res.unwrap_err().downcast_ref::<Error>(),
+            Some(Error::HostNotAllowed(host)) if host == "example.com:443"
+        ));
+    }
+
+    for version in [Version::HTTP_2, Version::HTTP_3] {
+        let extended_connect_res = reject_svc
+            .clone()
+            .oneshot(connect_request(version, "https://example.com:443/chat"))
+            .await;
+        assert!(extended_connect_res.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn authority_comparison() {
+    let lenient_svc = AllowedHostLayer::new("example.com").layer(service_fn(inner_svc));
+    let strict_svc = AllowedHostLayer::new("example.com")
+        .with_authority_comparison(AuthorityComparison::Strict)
+        .layer(service_fn(inner_svc));
+
+    for (host, lenient_ok) in [
+        ("example.com", true),
+        ("Example.com", true),
+        ("example.com:443", true),
+        ("example.com:8443", false),
+        ("example.net", false),
+    ] {
+        let lenient_res = lenient_svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .version(Version::HTTP_2)
+                    .uri("https://example.com/")
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(lenient_res.is_ok(), lenient_ok, "{host}");
+
+        let strict_res = strict_svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .version(Version::HTTP_2)
+                    .uri("https://example.com/")
+                    .header("HOST", host)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(strict_res.is_ok(), host == "example.com", "{host}");
+    }
+
+    let http_default_port_res = lenient_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .version(Version::HTTP_2)
+                .uri("http://example.com/")
+                .header("HOST", "example.com:443")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        http_default_port_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MismatchAuthorityHost)
+    ));
+}
+
+async fn host_source_svc(req: Request<BoxBody>) -> Result<Response<BoxBody>, Infallible> {
+    let host = req.extensions().get::<Host>().unwrap();
+    let source = req.extensions().get::<HostSource>().unwrap();
+    assert_eq!(host.0, "example.com");
+    assert_eq!(*source, HostSource::Default);
+    Ok(Response::builder().body(empty_body()).unwrap())
+}
+
+#[tokio::test]
+async fn default_host() {
+    let no_default_svc = AllowedHostLayer::new("example.com").layer(service_fn(inner_svc));
+    let default_svc = AllowedHostLayer::new("example.com")
+        .with_default_host(DefaultHost::new("example.com"))
+        .layer(service_fn(host_source_svc));
+    let http_09_svc = AllowedHostLayer::new("example.com")
+        .with_default_host(DefaultHost::new("example.com").with_http_09())
+        .layer(service_fn(host_source_svc));
+    let not_allowed_svc = AllowedHostLayer::new("example.com")
+        .with_default_host(DefaultHost::new("example.net"))
+        .layer(service_fn(inner_svc));
+
+    let no_default_res = no_default_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .version(Version::HTTP_10)
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        no_default_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::MissingHost)
+    ));
+
+    for (svc, version, ok) in [
+        (&default_svc, Version::HTTP_10, true),
+        (&default_svc, Version::HTTP_09, false),
+        (&default_svc, Version::HTTP_11, false),
+        (&http_09_svc, Version::HTTP_10, true),
+        (&http_09_svc, Version::HTTP_09, true),
+        (&http_09_svc, Version::HTTP_11, false),
+    ] {
+        let res = svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .version(version)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(res.is_ok(), ok, "{version:?}");
+    }
+
+    let not_allowed_res = not_allowed_svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .version(Version::HTTP_10)
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        not_allowed_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(host)) if host == "example.net"
+    ));
+}
+
+#[tokio::test]
+async fn forwarded_grammar() {
+    let svc = AllowedHostLayer::new("example.com")
+        .with_forwarded_matcher(("by", "proxy,1"))
+        .layer(service_fn(inner_svc));
+
+    let quoted_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header(
+                    "FORWARDED",
+                    r#"for="[2001:db8::1]:4711";by="proxy,1";host="example.com""#,
+                )
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(quoted_res.is_ok());
+
+    let split_quoted_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com")
+                .header("FORWARDED", r#"by="proxy;host=evil.com,1";host=evil.com"#)
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(split_quoted_res.is_ok());
+
+    let malformed_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com")
+                .header("FORWARDED", r#"by="proxy,1;host=example.com"#)
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        malformed_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::InvalidForwardedHeader(
+            InvalidForwardedKind::UnterminatedQuote
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn forwarded_ignored_without_matcher() {
+    let svc = AllowedHostLayer::new("example.com").layer(service_fn(inner_svc));
+
+    for forwarded in [
+        "for=[2001:db8::1]",
+        "for=a b",
+        r#"host="example.com"#,
+        "for=1.2.3.4;for=5.6.7.8",
+    ] {
+        let res = svc
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .header("HOST", "example.com")
+                    .header("FORWARDED", forwarded)
+                    .body(empty_body())
+                    .unwrap(),
+            )
+            .await;
+        assert!(res.is_ok(), "{forwarded}");
+    }
+}
+
+#[tokio::test]
+async fn forwarded_duplicate_parameter() {
+    let svc = AllowedHostLayer::new("example.com")
+        .with_forwarded_matcher(("signature", "random_value"))
+        .layer(service_fn(inner_svc));
+
+    let duplicate_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "example.com")
+                .header(
+                    "FORWARDED",
+                    "host=evil.com;signature=random_value;host=example.com",
+                )
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        duplicate_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::InvalidForwardedHeader(
+            InvalidForwardedKind::DuplicateParameter
+        ))
+    ));
+}
+
+fn forwarded_request(peer: Option<&str>) -> Request<BoxBody> {
+    let mut req = Request::builder()
+        .header("HOST", "127.0.0.1")
+        .header("FORWARDED", "for=192.0.2.43;host=example.com")
+        .body(empty_body())
+        .unwrap();
+    if let Some(peer) = peer {
+        req.extensions_mut()
+            .insert(peer.parse::<std::net::SocketAddr>().unwrap());
+    }
+    req
+}
+
+#[tokio::test]
+async fn trusted_proxies() {
+    let trusted_proxies = TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]);
+    let ignore_svc = AllowedHostLayer::new("example.com")
+        .with_forwarded_matcher(Any)
+        .with_trusted_proxies(trusted_proxies.clone())
+        .layer(service_fn(inner_svc));
+    let reject_svc = AllowedHostLayer::new(["example.com", "127.0.0.1"])
+        .with_forwarded_matcher(Any)
+        .with_trusted_proxies(trusted_proxies.with_untrusted(UntrustedForwarded::Reject))
+        .layer(service_fn(inner_svc));
+
+    let trusted_res = ignore_svc
+        .clone()
+        .oneshot(forwarded_request(Some("10.1.2.3:4711")))
+        .await;
+    assert!(trusted_res.is_ok());
+
+    let untrusted_res = ignore_svc
+        .clone()
+        .oneshot(forwarded_request(Some("192.0.2.1:4711")))
+        .await;
+    assert!(matches!(
+        untrusted_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(host)) if host == "127.0.0.1"
+    ));
+
+    let unknown_peer_res = ignore_svc.clone().oneshot(forwarded_request(None)).await;
+    assert!(unknown_peer_res.is_err());
+
+    let reject_res = reject_svc
+        .clone()
+        .oneshot(forwarded_request(Some("192.0.2.1:4711")))
+        .await;
+    assert!(matches!(
+        reject_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::UntrustedForwarded)
+    ));
+
+    let mut without_forwarded = Request::builder()
+        .header("HOST", "127.0.0.1")
+        .body(empty_body())
+        .unwrap();
+    without_forwarded
+        .extensions_mut()
+        .insert("192.0.2.1:4711".parse::<std::net::SocketAddr>().unwrap());
+    let without_forwarded_res = reject_svc.clone().oneshot(without_forwarded).await;
+    assert!(without_forwarded_res.is_ok());
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn trusted_proxies_connect_info() {
+    let svc = AllowedHostLayer::new("example.com")
+        .with_forwarded_matcher(Any)
+        .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]))
+        .layer(service_fn(inner_svc));
+
+    let mut req = forwarded_request(None);
+    req.extensions_mut().insert(axum::extract::ConnectInfo(
+        "10.1.2.3:4711".parse::<std::net::SocketAddr>().unwrap(),
+    ));
+    let res = svc.oneshot(req).await;
+    assert!(res.is_ok());
+}
+
+fn chain_request(peer: &str) -> Request<BoxBody> {
+    let mut req = Request::builder()
+        .header("HOST", "127.0.0.1")
+        .header("FORWARDED", "for=1.2.3.4;host=evil.com")
+        .header(
+            "FORWARDED",
+            "for=203.0.113.9;host=example.com, for=10.0.0.1;host=internal.example.com",
+        )
+        .body(empty_body())
+        .unwrap();
+    req.extensions_mut()
+        .insert(peer.parse::<std::net::SocketAddr>().unwrap());
+    req
+}
+
+#[tokio::test]
+async fn hop_selection() {
+    for (hop_selection, peer, blocked_host) in [
+        (HopSelection::FirstMatch, "10.0.0.2:4711", Some("evil.com")),
+        (
+            HopSelection::Rightmost,
+            "10.0.0.2:4711",
+            Some("internal.example.com"),
+        ),
+        (HopSelection::TrustedHops(2), "10.0.0.2:4711", None),
+        (
+            HopSelection::TrustedHops(0),
+            "10.0.0.2:4711",
+            Some("127.0.0.1"),
+        ),
+        (
+            HopSelection::TrustedHops(4),
+            "10.0.0.2:4711",
+            Some("127.0.0.1"),
+        ),
+        (HopSelection::TrustedChain, "10.0.0.2:4711", None),
+        (
+            HopSelection::TrustedChain,
+            "192.0.2.1:4711",
+            Some("127.0.0.1"),
+        ),
+    ] {
+        let svc = AllowedHostLayer::new("example.com")
+            .with_forwarded_matcher(Any)
+            .with_hop_selection(hop_selection)
+            .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]))
+            .layer(service_fn(inner_svc));
+        let res = svc.oneshot(chain_request(peer)).await;
+        match blocked_host {
+            Some(blocked_host) => {
+                assert!(
+                    matches!(
+                        res.unwrap_err().downcast_ref::<Error>(),
+                        Some(Error::HostNotAllowed(host)) if host == blocked_host
+                    ),
+                    "{hop_selection:?}"
+                );
+            }
+            None => assert!(res.is_ok(), "{hop_selection:?}"),
+        }
+    }
+
+    let no_trusted_proxies_svc = AllowedHostLayer::new("127.0.0.1")
+        .with_forwarded_matcher(Any)
+        .with_hop_selection(HopSelection::TrustedChain)
+        .layer(service_fn(inner_svc));
+    let no_trusted_proxies_res = no_trusted_proxies_svc
+        .oneshot(chain_request("10.0.0.2:4711"))
+        .await;
+    assert!(no_trusted_proxies_res.is_ok());
+
+    let rejected_by_matcher_svc = AllowedHostLayer::new("127.0.0.1")
+        .with_forwarded_matcher(("for", "1.2.3.4"))
+        .with_hop_selection(HopSelection::Rightmost)
+        .layer(service_fn(inner_svc));
+    let rejected_by_matcher_res = rejected_by_matcher_svc
+        .oneshot(chain_request("10.0.0.2:4711"))
+        .await;
+    assert!(rejected_by_matcher_res.is_ok());
+}
+
+struct LoadBalancers;
+
+impl ForwardedChainMatcher for LoadBalancers {
+    fn select<'a>(&self, chain: &ForwardedChain<'a>) -> Option<Parameters<'a>> {
+        match chain.elements() {
+            [.., outer, inner]
+                if outer.get("by") == Some("_lb1") && inner.get("by") == Some("_lb2") =>
+            {
+                Some(*outer)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[tokio::test]
+async fn forwarded_chain_matcher() {
+    let svc = AllowedHostLayer::new("example.com")
+        .with_forwarded_matcher(Any)
+        .with_forwarded_chain_matcher(LoadBalancers)
+        .layer(service_fn(inner_svc));
+
+    let trusted_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "127.0.0.1")
+                .header(
+                    "FORWARDED",
+                    "host=evil.com, by=_lb1;host=example.com, by=_lb2;host=internal.example.com",
+                )
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(trusted_res.is_ok());
+
+    let single_hop_res = svc
+        .clone()
+        .oneshot(
+            Request::builder()
+                .header("HOST", "127.0.0.1")
+                .header("FORWARDED", "by=_lb2;host=example.com")
+                .body(empty_body())
+                .unwrap(),
+        )
+        .await;
+    assert!(matches!(
+        single_hop_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(host)) if host == "127.0.0.1"
+    ));
+}
+
+#[cfg(feature = "signed")]
+#[tokio::test]
+async fn signed_forwarded() {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::matcher::SignedForwarded;
+
+    let signed_header = |matcher: &SignedForwarded, host: &str, ts: u64| {
+        let pairs = vec![
+            ("host".to_string(), host.to_string()),
+            ("ts".to_string(), ts.to_string()),
+        ];
+        let signature = matcher.sign(&Parameters::new(&pairs));
+        format!("host={host};ts={ts};signature={signature}")
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let matcher = SignedForwarded::new(b"current").with_timestamp("ts", Duration::from_secs(30));
+    let svc = AllowedHostLayer::new("example.com")
+        .with_forwarded_matcher(matcher.clone().with_key(b"previous"))
+        .layer(service_fn(inner_svc));
+
+    let request = |forwarded: String| {
+        Request::builder()
+            .header("HOST", "127.0.0.1")
+            .header("FORWARDED", forwarded)
+            .body(empty_body())
+            .unwrap()
+    };
+
+    let signed_res = svc
+        .clone()
+        .oneshot(request(signed_header(&matcher, "example.com", now)))
+        .await;
+    assert!(signed_res.is_ok());
+
+    let previous = SignedForwarded::new(b"previous");
+    let rotated_res = svc
+        .clone()
+        .oneshot(request(signed_header(&previous, "example.com", now)))
+        .await;
+    assert!(rotated_res.is_ok());
+
+    let unknown = SignedForwarded::new(b"unknown");
+    let unknown_key_res = svc
+        .clone()
+        .oneshot(request(signed_header(&unknown, "example.com", now)))
+        .await;
+    assert!(matches!(
+        unknown_key_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(host)) if host == "127.0.0.1"
+    ));
+
+    let replayed_res = svc
+        .clone()
+        .oneshot(request(signed_header(&matcher, "example.com", now - 3600)))
+        .await;
+    assert!(matches!(
+        replayed_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(host)) if host == "127.0.0.1"
+    ));
+
+    let tampered = signed_header(&matcher, "example.com", now).replace("example.com", "evil.com");
+    let tampered_res = svc.clone().oneshot(request(tampered)).await;
+    assert!(matches!(
+        tampered_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::HostNotAllowed(host)) if host == "127.0.0.1"
+    ));
+}
+
+async fn echo_host_svc(req: Request<BoxBody>) -> Result<Response<BoxBody>, Infallible> {
+    let host = req.extensions().get::<Host>().unwrap();
+    let source = req.extensions().get::<HostSource>().unwrap();
+    Ok(Response::builder()
+        .header("x-host", host.0.as_str())
+        .header("x-source", format!("{source:?}"))
+        .body(empty_body())
+        .unwrap())
+}
+
+fn x_forwarded_request(peer: &str, headers: &[(&str, &str)]) -> Request<BoxBody> {
+    let mut req = Request::builder().header("HOST", "127.0.0.1");
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let mut req = req.body(empty_body()).unwrap();
+    req.extensions_mut()
+        .insert(peer.parse::<std::net::SocketAddr>().unwrap());
+    req
+}
+
+fn resolved_host(res: Result<Response<BoxBody>, BoxError>) -> (String, String) {
+    let res = res.unwrap();
+    (
+        res.headers()["x-host"].to_str().unwrap().to_string(),
+        res.headers()["x-source"].to_str().unwrap().to_string(),
+    )
+}
+
+const X_FORWARDED_HOSTS: [&str; 4] = [
+    "example.com",
+    "example.com:8080",
+    "forwarded.example.com",
+    "127.0.0.1",
+];
+
+#[tokio::test]
+async fn x_forwarded() {
+    let trusted_proxies = TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]);
+    let svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_x_forwarded(XForwarded::new())
+        .with_trusted_proxies(trusted_proxies.clone())
+        .layer(service_fn(echo_host_svc));
+    let trusted_chain_svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_x_forwarded(XForwarded::new().with_hop_selection(HopSelection::TrustedChain))
+        .with_trusted_proxies(trusted_proxies.clone())
+        .layer(service_fn(echo_host_svc));
+    let disabled_svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_trusted_proxies(trusted_proxies)
+        .layer(service_fn(echo_host_svc));
+
+    let rightmost_res = svc
+        .clone()
+        .oneshot(x_forwarded_request(
+            "10.0.0.2:4711",
+            &[
+                ("X-Forwarded-Host", "evil.com, example.com"),
+                ("X-Forwarded-Proto", "https"),
+                ("X-Forwarded-Port", "443"),
+            ],
+        ))
+        .await;
+    assert_eq!(
+        resolved_host(rightmost_res),
+        ("example.com".to_string(), "XForwarded".to_string())
+    );
+
+    let port_res = svc
+        .clone()
+        .oneshot(x_forwarded_request(
+            "10.0.0.2:4711",
+            &[
+                ("X-Forwarded-Host", "example.com"),
+                ("X-Forwarded-Proto", "http"),
+                ("X-Forwarded-Port", "8080"),
+            ],
+        ))
+        .await;
+    assert_eq!(resolved_host(port_res).0, "example.com:8080");
+
+    let untrusted_res = svc
+        .clone()
+        .oneshot(x_forwarded_request(
+            "192.0.2.1:4711",
+            &[("X-Forwarded-Host", "example.com")],
+        ))
+        .await;
+    assert_eq!(
+        resolved_host(untrusted_res),
+        ("127.0.0.1".to_string(), "Host".to_string())
+    );
+
+    let trusted_chain_res = trusted_chain_svc
+        .clone()
+        .oneshot(x_forwarded_request(
+            "10.0.0.2:4711",
+            &[
+                (
+                    "X-Forwarded-Host",
+                    "evil.com, example.com, internal.example.com",
+                ),
+                ("X-Forwarded-For", "1.2.3.4, 203.0.113.9, 10.0.0.1"),
+            ],
+        ))
+        .await;
+    assert_eq!(resolved_host(trusted_chain_res).0, "example.com");
+
+    let disabled_res = disabled_svc
+        .clone()
+        .oneshot(x_forwarded_request(
+            "10.0.0.2:4711",
+            &[("X-Forwarded-Host", "example.com")],
+        ))
+        .await;
+    assert_eq!(
+        resolved_host(disabled_res),
+        ("127.0.0.1".to_string(), "Host".to_string())
+    );
+}
+
+#[tokio::test]
+async fn x_forwarded_requires_trusted_proxies() {
+    let without_proxies_svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_x_forwarded(XForwarded::new())
+        .layer(service_fn(echo_host_svc));
+    let svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_x_forwarded(XForwarded::new())
+        .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]))
+        .layer(service_fn(echo_host_svc));
+
+    let spoofed_res = without_proxies_svc
+        .clone()
+        .oneshot(x_forwarded_request(
+            "203.0.113.5:4711",
+            &[("X-Forwarded-Host", "forwarded.example.com")],
+        ))
+        .await;
+    assert_eq!(
+        resolved_host(spoofed_res),
+        ("127.0.0.1".to_string(), "Host".to_string())
+    );
+
+    for port in ["http", "+80", "65536", ""] {
+        let invalid_port_res = svc
+            .clone()
+            .oneshot(x_forwarded_request(
+                "10.0.0.2:4711",
+                &[
+                    ("X-Forwarded-Host", "example.com"),
+                    ("X-Forwarded-Port", port),
+                ],
+            ))
+            .await;
+        if port.is_empty() {
+            assert_eq!(resolved_host(invalid_port_res).0, "example.com");
+        } else {
+            assert!(
+                matches!(
+                    invalid_port_res.unwrap_err().downcast_ref::<Error>(),
+                    Some(Error::InvalidHost(InvalidHostKind::InvalidPort))
+                ),
+                "{port}"
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn x_forwarded_invalid_header() {
+    let svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_x_forwarded(XForwarded::new())
+        .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]))
+        .layer(service_fn(echo_host_svc));
+
+    for name in ["x-forwarded-for", "x-forwarded-proto", "x-forwarded-port"] {
+        let mut req = x_forwarded_request("10.0.0.2:4711", &[("X-Forwarded-Host", "example.com")]);
+        req.headers_mut().insert(
+            http::HeaderName::from_static(name),
+            http::HeaderValue::from_bytes(b"caf\xc3\xa9").unwrap(),
+        );
+        let res = svc.clone().oneshot(req).await;
+        assert!(matches!(
+            res.unwrap_err().downcast_ref::<Error>(),
+            Some(Error::InvalidXForwardedHeader(header)) if header == name
+        ));
+    }
+}
+
+#[tokio::test]
+async fn x_forwarded_precedence() {
+    let trusted_proxies = TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]);
+    let forwarded_first_svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_forwarded_matcher(Any)
+        .with_x_forwarded(XForwarded::new())
+        .with_trusted_proxies(trusted_proxies.clone())
+        .layer(service_fn(echo_host_svc));
+    let x_forwarded_first_svc = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_forwarded_matcher(Any)
+        .with_x_forwarded(XForwarded::new().with_precedence(ForwardedPrecedence::XForwarded))
+        .with_trusted_proxies(trusted_proxies.with_untrusted(UntrustedForwarded::Reject))
+        .layer(service_fn(echo_host_svc));
+
+    let both = [
+        ("Forwarded", "host=forwarded.example.com"),
+        ("X-Forwarded-Host", "example.com"),
+    ];
+    let forwarded_first_res = forwarded_first_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &both))
+        .await;
+    assert_eq!(
+        resolved_host(forwarded_first_res),
+        ("forwarded.example.com".to_string(), "Forwarded".to_string())
+    );
+    let x_forwarded_first_res = x_forwarded_first_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &both))
+        .await;
+    assert_eq!(
+        resolved_host(x_forwarded_first_res),
+        ("example.com".to_string(), "XForwarded".to_string())
+    );
+
+    let only_forwarded_res = x_forwarded_first_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &both[..1]))
+        .await;
+    assert_eq!(
+        resolved_host(only_forwarded_res),
+        ("forwarded.example.com".to_string(), "Forwarded".to_string())
+    );
+
+    let untrusted_res = x_forwarded_first_svc
+        .clone()
+        .oneshot(x_forwarded_request("192.0.2.1:4711", &both[1..]))
+        .await;
+    assert!(matches!(
+        untrusted_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::UntrustedForwarded)
+    ));
+}
+
+#[tokio::test]
+async fn host_conflict() {
+    let trusted_proxies = TrustedProxies::new(["10.0.0.0/8".parse().unwrap()]);
+    let layer = AllowedHostLayer::new(X_FORWARDED_HOSTS)
+        .with_forwarded_matcher(Any)
+        .with_x_forwarded(XForwarded::new())
+        .with_trusted_proxies(trusted_proxies);
+    let reject_svc = layer
+        .clone()
+        .with_host_conflict(HostConflict::new(ConflictAction::Reject))
+        .layer(service_fn(echo_host_svc));
+    let request_host_svc = layer
+        .clone()
+        .with_host_conflict(HostConflict::new(ConflictAction::Reject).with_request_host())
+        .layer(service_fn(echo_host_svc));
+    let warn_svc = layer
+        .with_host_conflict(HostConflict::new(ConflictAction::Warn))
+        .layer(service_fn(echo_host_svc));
+
+    let conflicting = [
+        ("Forwarded", "host=forwarded.example.com"),
+        ("X-Forwarded-Host", "example.com"),
+    ];
+    let reject_res = reject_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &conflicting))
+        .await;
+    let reject_err = reject_res.unwrap_err();
+    assert!(matches!(
+        reject_err.downcast_ref::<Error>(),
+        Some(Error::ConflictingHost(hosts)) if hosts == &[
+            (HostSource::Forwarded, "forwarded.example.com".to_string()),
+            (HostSource::XForwarded, "example.com".to_string()),
+        ]
+    ));
+    assert_eq!(
+        reject_err.to_string(),
+        "conflicting host: forwarded forwarded.example.com, x-forwarded-host example.com"
+    );
+
+    let warn_res = warn_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &conflicting))
+        .await;
+    assert_eq!(
+        resolved_host(warn_res),
+        ("forwarded.example.com".to_string(), "Forwarded".to_string())
+    );
+
+    let agreeing = [
+        ("Forwarded", "host=example.com"),
+        ("X-Forwarded-Host", "Example.com"),
+        ("X-Forwarded-Port", "8080"),
+    ];
+    let agreeing_res = reject_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &agreeing))
+        .await;
+    assert!(agreeing_res.is_ok());
+
+    let request_host_res = request_host_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &agreeing))
+        .await;
+    let request_host = (HostSource::Host, "127.0.0.1".to_string());
+    assert!(matches!(
+        request_host_res.unwrap_err().downcast_ref::<Error>(),
+        Some(Error::ConflictingHost(hosts)) if hosts.last() == Some(&request_host)
+    ));
+
+    let without_forwarded_res = request_host_svc
+        .clone()
+        .oneshot(x_forwarded_request("10.0.0.2:4711", &[]))
+        .await;
+    assert_eq!(
+        resolved_host(without_forwarded_res),
+        ("127.0.0.1".to_string(), "Host".to_string())
+    );
+}